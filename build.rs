@@ -0,0 +1,30 @@
+//! Build metadata printed by `--version`, the commit is omitted when not built from a git checkout.
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let target = std::env::var("TARGET").unwrap_or_default();
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .filter(|commit| !commit.is_empty());
+    let details = commit.into_iter().chain([target]).collect::<Vec<_>>();
+
+    println!(
+        "cargo:rustc-env=UPDATE_CHROME_DRIVER_LONG_VERSION={} ({})",
+        env!("CARGO_PKG_VERSION"),
+        details.join(" ")
+    );
+
+    for path in [".git/HEAD", ".git/refs/heads"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
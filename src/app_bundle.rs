@@ -0,0 +1,155 @@
+//! Version of the macOS applications read from the Info.plist of their bundle, which is
+//! faster and more reliable than running them with `--version`.
+use crate::{parsers, Error, Version};
+use nom::branch::alt;
+use nom::combinator::all_consuming;
+use nom::Finish;
+use std::path::{Path, PathBuf};
+
+/// Chrome stores its full version in `KSVersion`, the other browsers only have the standard key.
+const VERSION_KEYS: &[&str] = &["KSVersion", "CFBundleShortVersionString"];
+
+/// The bundle of `path`, which is either the `.app` directory itself or the executable
+/// in its `Contents/MacOS` directory.
+pub fn get_bundle_directory(path: &Path) -> Option<PathBuf> {
+    let is_bundle = |path: &Path| path.extension().is_some_and(|extension| extension == "app");
+
+    if is_bundle(path) {
+        return Some(path.to_path_buf());
+    }
+
+    let macos_directory = path.parent()?;
+    let contents_directory = macos_directory.parent()?;
+    let bundle = contents_directory.parent()?;
+
+    (macos_directory.file_name()? == "MacOS"
+        && contents_directory.file_name()? == "Contents"
+        && is_bundle(bundle))
+    .then(|| bundle.to_path_buf())
+}
+
+/// The executable of `bundle` in its `Contents/MacOS` directory, named by the
+/// `CFBundleExecutable` key of its Info.plist, or like the bundle, e.g. `Google Chrome`.
+pub fn get_bundle_executable(bundle: &Path) -> Option<PathBuf> {
+    let contents_directory = bundle.join("Contents");
+    let name = plist::Value::from_file(contents_directory.join("Info.plist"))
+        .ok()
+        .and_then(|info| {
+            info.as_dictionary()?
+                .get("CFBundleExecutable")?
+                .as_string()
+                .map(String::from)
+        })
+        .or_else(|| Some(bundle.file_stem()?.to_string_lossy().into_owned()))?;
+    let executable = contents_directory.join("MacOS").join(name);
+
+    executable.is_file().then_some(executable)
+}
+
+pub fn get_bundle_version(bundle: &Path) -> Result<Version, Error> {
+    let info_path = bundle.join("Contents").join("Info.plist");
+    let info = plist::Value::from_file(&info_path).map_err(|error| {
+        Error::ParsingVersionFailed(format!("{}: {}", info_path.display(), error))
+    })?;
+
+    parse_info(&info)
+}
+
+fn parse_info(info: &plist::Value) -> Result<Version, Error> {
+    let dictionary = info.as_dictionary().ok_or_else(|| {
+        Error::ParsingVersionFailed(String::from("Info.plist is not a dictionary"))
+    })?;
+    let version = VERSION_KEYS
+        .iter()
+        .find_map(|key| dictionary.get(key).and_then(plist::Value::as_string))
+        .ok_or_else(|| Error::ParsingVersionFailed(String::from("Info.plist has no version")))?;
+
+    // Firefox versions have two or three components, like `120.0.1`.
+    all_consuming(alt((
+        parsers::parse_partial_version_numbers,
+        parsers::parse_release_version_numbers,
+    )))(version)
+    .finish()
+    .map(|(_, version)| version)
+    .map_err(|_| Error::ParsingVersionFailed(format!("'{}' is not a valid version", version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_bundle::{
+        get_bundle_directory, get_bundle_executable, get_bundle_version, parse_info,
+    };
+    use crate::Version;
+    use std::path::{Path, PathBuf};
+    use test_case::test_case;
+
+    const CHROME_INFO: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>CFBundleExecutable</key>
+    <string>Google Chrome</string>
+    <key>CFBundleShortVersionString</key>
+    <string>120.0.6099.109</string>
+    <key>KSVersion</key>
+    <string>120.0.6099.109</string>
+</dict>
+</plist>"#;
+
+    #[test_case("/Applications/Google Chrome.app", Some("/Applications/Google Chrome.app") ; "bundle")]
+    #[test_case("/Applications/Google Chrome.app/Contents/MacOS/Google Chrome", Some("/Applications/Google Chrome.app") ; "executable")]
+    #[test_case("/usr/bin/google-chrome", None ; "not in a bundle")]
+    #[test_case("/Applications/Google Chrome.app/Contents/Frameworks/chrome", None ; "other directory")]
+    fn test_get_bundle_directory(path: &str, expected: Option<&str>) {
+        assert_eq!(
+            expected.map(PathBuf::from),
+            get_bundle_directory(Path::new(path))
+        );
+    }
+
+    #[test_case(CHROME_INFO, Some(Version::new(120, 0, 6099, 109)) ; "chrome")]
+    #[test_case("<plist><dict><key>CFBundleShortVersionString</key><string>120.0.1</string></dict></plist>", Some(Version::new(120, 0, 1, 0)) ; "firefox")]
+    #[test_case("<plist><dict><key>CFBundleShortVersionString</key><string>121.0</string></dict></plist>", Some(Version::new(121, 0, 0, 0)) ; "two components")]
+    #[test_case("<plist><dict><key>CFBundleName</key><string>Chrome</string></dict></plist>", None ; "no version")]
+    #[test_case("<plist><string>120.0.6099.109</string></plist>", None ; "not a dictionary")]
+    fn test_parse_info(input: &str, expected: Option<Version>) {
+        let info = plist::Value::from_reader_xml(input.as_bytes()).unwrap();
+
+        assert_eq!(expected, parse_info(&info).ok());
+    }
+
+    #[test]
+    fn test_get_bundle_version() {
+        let directory = tempfile::tempdir().unwrap();
+        let bundle = directory.path().join("Google Chrome.app");
+
+        std::fs::create_dir_all(bundle.join("Contents")).unwrap();
+        std::fs::write(bundle.join("Contents").join("Info.plist"), CHROME_INFO).unwrap();
+
+        assert_eq!(
+            Version::new(120, 0, 6099, 109),
+            get_bundle_version(&bundle).unwrap()
+        );
+    }
+
+    #[test_case(Some(CHROME_INFO), "Google Chrome", true ; "named in info")]
+    #[test_case(None, "Google Chrome", true ; "named like the bundle")]
+    #[test_case(Some("<plist><dict><key>CFBundleExecutable</key><string>Chromium</string></dict></plist>"), "Google Chrome", false ; "missing executable")]
+    fn test_get_bundle_executable(info: Option<&str>, executable_name: &str, is_found: bool) {
+        let directory = tempfile::tempdir().unwrap();
+        let bundle = directory.path().join("Google Chrome.app");
+        let executable = bundle.join("Contents/MacOS").join(executable_name);
+
+        std::fs::create_dir_all(executable.parent().unwrap()).unwrap();
+        std::fs::write(&executable, "").unwrap();
+
+        if let Some(info) = info {
+            std::fs::write(bundle.join("Contents/Info.plist"), info).unwrap();
+        }
+
+        assert_eq!(
+            Some(executable).filter(|_| is_found),
+            get_bundle_executable(&bundle)
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{Error, Platform};
+use serde::{Deserialize, Deserializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A browser and its driver.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum Browser {
+    /// Google Chrome and chromedriver.
+    #[default]
+    Chrome,
+    /// Microsoft Edge and msedgedriver.
+    Edge,
+    /// Mozilla Firefox and geckodriver.
+    Firefox,
+}
+
+impl Browser {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Browser::Chrome => "Google Chrome",
+            Browser::Edge => "Microsoft Edge",
+            Browser::Firefox => "Mozilla Firefox",
+        }
+    }
+
+    pub fn get_driver_executable_name(self, platform: Platform) -> &'static str {
+        match (self, platform) {
+            (Browser::Chrome, _) => platform.get_chromedriver_executable_name(),
+            (Browser::Edge, Platform::Windows | Platform::Windows64) => "msedgedriver.exe",
+            (Browser::Edge, _) => "msedgedriver",
+            (Browser::Firefox, Platform::Windows | Platform::Windows64) => "geckodriver.exe",
+            (Browser::Firefox, _) => "geckodriver",
+        }
+    }
+
+    /// The names the driver may have in an archive or in the output directory, the standard one
+    /// first, then the ones suffixed with an architecture like `chromedriver-linux64`.
+    pub fn get_driver_executable_names(self, platform: Platform) -> Vec<String> {
+        let executable_name = self.get_driver_executable_name(platform);
+        let (stem, extension) = match executable_name.strip_suffix(".exe") {
+            Some(stem) => (stem, ".exe"),
+            None => (executable_name, ""),
+        };
+        let keys = match self {
+            Browser::Chrome => vec![platform.get_chrome_for_testing_key(), platform.get_key()],
+            Browser::Edge => vec![platform.get_edge_key()],
+            Browser::Firefox => vec![platform.get_geckodriver_key()],
+        };
+        let mut names = vec![executable_name.to_string()];
+
+        for key in keys {
+            let name = format!("{}-{}{}", stem, key, extension);
+
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+}
+
+impl FromStr for Browser {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "chrome" => Ok(Browser::Chrome),
+            "edge" => Ok(Browser::Edge),
+            "firefox" => Ok(Browser::Firefox),
+            _ => Err(Error::UnsupportedBrowser(input.to_string())),
+        }
+    }
+}
+
+/// Deserialized from the same names as [`FromStr`].
+impl<'de> Deserialize<'de> for Browser {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Display for Browser {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Browser, Platform};
+    use test_case::test_case;
+
+    #[test_case("chrome", Some(Browser::Chrome) ; "chrome")]
+    #[test_case("edge", Some(Browser::Edge) ; "edge")]
+    #[test_case("firefox", Some(Browser::Firefox) ; "firefox")]
+    #[test_case("safari", None ; "unsupported")]
+    fn test_browser_from_str(input: &str, expected: Option<Browser>) {
+        assert_eq!(expected, input.parse::<Browser>().ok());
+    }
+
+    #[test_case(Browser::Chrome, Platform::Linux, "chromedriver" ; "chrome linux")]
+    #[test_case(Browser::Chrome, Platform::Windows64, "chromedriver.exe" ; "chrome windows")]
+    #[test_case(Browser::Edge, Platform::MacOsArm64, "msedgedriver" ; "edge macos")]
+    #[test_case(Browser::Edge, Platform::Windows, "msedgedriver.exe" ; "edge windows")]
+    #[test_case(Browser::Firefox, Platform::Linux, "geckodriver" ; "firefox linux")]
+    #[test_case(Browser::Firefox, Platform::Windows64, "geckodriver.exe" ; "firefox windows")]
+    fn test_driver_executable_name(browser: Browser, platform: Platform, expected: &str) {
+        assert_eq!(expected, browser.get_driver_executable_name(platform));
+    }
+
+    #[test_case(Browser::Chrome, Platform::Linux, &["chromedriver", "chromedriver-linux64"] ; "chrome linux")]
+    #[test_case(Browser::Chrome, Platform::LinuxArm64, &["chromedriver", "chromedriver-linux-arm64"] ; "chrome linux arm64")]
+    #[test_case(Browser::Chrome, Platform::MacOsArm64, &["chromedriver", "chromedriver-mac-arm64", "chromedriver-mac_arm64"] ; "chrome macos arm64")]
+    #[test_case(Browser::Chrome, Platform::Windows64, &["chromedriver.exe", "chromedriver-win64.exe", "chromedriver-win32.exe"] ; "chrome windows")]
+    #[test_case(Browser::Edge, Platform::MacOsArm64, &["msedgedriver", "msedgedriver-mac64_m1"] ; "edge macos arm64")]
+    #[test_case(Browser::Firefox, Platform::Windows, &["geckodriver.exe", "geckodriver-win32.exe"] ; "firefox windows")]
+    fn test_driver_executable_names(browser: Browser, platform: Platform, expected: &[&str]) {
+        assert_eq!(expected, browser.get_driver_executable_names(platform));
+    }
+}
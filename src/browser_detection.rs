@@ -0,0 +1,430 @@
+//! Finds the browser executable when its location is not specified.
+#[cfg(target_os = "linux")]
+use crate::flatpak;
+use crate::{get_browser_channel, Browser, Channel, Error, Version};
+use log::debug;
+use std::path::{Path, PathBuf};
+
+#[cfg(target_os = "linux")]
+fn get_standard_install_paths(browser: Browser) -> &'static [&'static str] {
+    match browser {
+        Browser::Chrome => &[
+            "/usr/bin/google-chrome",
+            "/usr/bin/google-chrome-stable",
+            "/opt/google/chrome/chrome",
+            // Ubuntu only packages Chromium as a snap.
+            "/snap/bin/chromium",
+            "/usr/bin/google-chrome-beta",
+            "/opt/google/chrome-beta/chrome",
+            "/usr/bin/google-chrome-unstable",
+            "/opt/google/chrome-unstable/chrome",
+        ],
+        Browser::Edge => &[
+            "/usr/bin/microsoft-edge",
+            "/usr/bin/microsoft-edge-stable",
+            "/opt/microsoft/msedge/msedge",
+            "/usr/bin/microsoft-edge-beta",
+            "/opt/microsoft/msedge-beta/msedge",
+            "/usr/bin/microsoft-edge-dev",
+            "/opt/microsoft/msedge-dev/msedge",
+        ],
+        Browser::Firefox => &[
+            "/usr/bin/firefox",
+            "/usr/lib/firefox/firefox",
+            "/opt/firefox/firefox",
+            "/snap/bin/firefox",
+        ],
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn get_standard_install_paths(browser: Browser) -> &'static [&'static str] {
+    match browser {
+        Browser::Chrome => &[
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "/Applications/Google Chrome Beta.app/Contents/MacOS/Google Chrome Beta",
+            "/Applications/Google Chrome Dev.app/Contents/MacOS/Google Chrome Dev",
+            "/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+        ],
+        Browser::Edge => &[
+            "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+            "/Applications/Microsoft Edge Beta.app/Contents/MacOS/Microsoft Edge Beta",
+            "/Applications/Microsoft Edge Dev.app/Contents/MacOS/Microsoft Edge Dev",
+            "/Applications/Microsoft Edge Canary.app/Contents/MacOS/Microsoft Edge Canary",
+        ],
+        Browser::Firefox => &["/Applications/Firefox.app/Contents/MacOS/firefox"],
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn get_standard_install_paths(browser: Browser) -> &'static [&'static str] {
+    match browser {
+        Browser::Chrome => &[
+            "C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe",
+            "C:\\Program Files (x86)\\Google\\Chrome\\Application\\chrome.exe",
+            "C:\\Program Files\\Google\\Chrome Beta\\Application\\chrome.exe",
+            "C:\\Program Files\\Google\\Chrome Dev\\Application\\chrome.exe",
+        ],
+        Browser::Edge => &[
+            "C:\\Program Files (x86)\\Microsoft\\Edge\\Application\\msedge.exe",
+            "C:\\Program Files\\Microsoft\\Edge\\Application\\msedge.exe",
+            "C:\\Program Files (x86)\\Microsoft\\Edge Beta\\Application\\msedge.exe",
+            "C:\\Program Files (x86)\\Microsoft\\Edge Dev\\Application\\msedge.exe",
+        ],
+        Browser::Firefox => &[
+            "C:\\Program Files\\Mozilla Firefox\\firefox.exe",
+            "C:\\Program Files (x86)\\Mozilla Firefox\\firefox.exe",
+        ],
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn get_standard_install_paths(_browser: Browser) -> &'static [&'static str] {
+    &[]
+}
+
+/// The names looked up in the `PATH`.
+#[cfg(not(target_os = "windows"))]
+fn get_program_names(browser: Browser) -> &'static [&'static str] {
+    match browser {
+        Browser::Chrome => &[
+            "google-chrome",
+            "google-chrome-stable",
+            "google-chrome-beta",
+            "google-chrome-unstable",
+        ],
+        Browser::Edge => &[
+            "microsoft-edge",
+            "microsoft-edge-stable",
+            "microsoft-edge-beta",
+            "microsoft-edge-dev",
+        ],
+        Browser::Firefox => &["firefox"],
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn get_program_names(browser: Browser) -> &'static [&'static str] {
+    match browser {
+        Browser::Chrome => &["chrome"],
+        Browser::Edge => &["msedge"],
+        Browser::Firefox => &["firefox"],
+    }
+}
+
+/// Find the browser in the standard install locations of the current platform,
+/// then in the `PATH`, then on Linux in the launchers of its Flatpak.
+/// When several channels are installed the stable one is picked, then beta, dev and canary,
+/// unless `preferred_channel` is installed. When several installations of the same channel are
+/// found, the newest one is picked.
+pub fn find_browser(browser: Browser, preferred_channel: Channel) -> Result<PathBuf, Error> {
+    let mut candidates = get_standard_paths(browser);
+
+    candidates.extend(
+        get_program_names(browser)
+            .iter()
+            .filter_map(|name| which::which(name).ok()),
+    );
+
+    #[cfg(target_os = "linux")]
+    candidates.extend(
+        flatpak::get_default_app_ids(browser)
+            .iter()
+            .flat_map(|app_id| flatpak::get_launcher_paths(app_id)),
+    );
+
+    select_browser(browser, candidates, preferred_channel, |path| {
+        crate::get_local_browser_version(path, browser).ok()
+    })
+}
+
+/// The channels from the most to the least preferred.
+fn get_channel_priority(preferred_channel: Channel) -> impl Iterator<Item = Channel> {
+    std::iter::once(preferred_channel).chain(
+        [
+            Channel::Stable,
+            Channel::Beta,
+            Channel::Dev,
+            Channel::Canary,
+        ]
+        .into_iter()
+        .filter(move |channel| *channel != preferred_channel),
+    )
+}
+
+/// Pick one of the existing `candidates` with the priority of [`find_browser`], the launchers of
+/// the same installation like `/usr/bin/google-chrome` and `/usr/bin/google-chrome-stable` are
+/// only considered once.
+fn select_browser<F>(
+    browser: Browser,
+    candidates: Vec<PathBuf>,
+    preferred_channel: Channel,
+    get_version: F,
+) -> Result<PathBuf, Error>
+where
+    F: Fn(&Path) -> Option<Version>,
+{
+    let mut installations: Vec<(PathBuf, PathBuf, Channel)> = Vec::new();
+
+    for candidate in &candidates {
+        debug!("Looking for {} at '{}'", browser, candidate.display());
+
+        if !candidate.is_file() {
+            continue;
+        }
+
+        // The channel of a launcher like `/usr/bin/google-chrome` is the one of its target.
+        let target = std::fs::canonicalize(candidate).unwrap_or_else(|_| candidate.clone());
+
+        if installations.iter().any(|(_, other, _)| *other == target) {
+            continue;
+        }
+
+        let channel = get_browser_channel(&target, "");
+
+        debug!("Found {} {} at '{}'", browser, channel, candidate.display());
+        installations.push((candidate.clone(), target, channel));
+    }
+
+    let Some(channel) = get_channel_priority(preferred_channel)
+        .find(|channel| installations.iter().any(|(_, _, other)| other == channel))
+    else {
+        return Err(Error::BrowserNotFound(browser, candidates));
+    };
+    let mut installations: Vec<PathBuf> = installations
+        .into_iter()
+        .filter(|(_, _, other)| *other == channel)
+        .map(|(path, _, _)| path)
+        .collect();
+
+    if installations.len() == 1 {
+        return Ok(installations.remove(0));
+    }
+
+    // The first of the newest, an installation whose version is unknown comes last.
+    let versions: Vec<Option<Version>> =
+        installations.iter().map(|path| get_version(path)).collect();
+    let newest = (0..installations.len())
+        .rev()
+        .max_by_key(|index| versions[*index])
+        .unwrap_or_default();
+
+    debug!(
+        "Picked the newest of {} installations of {} {}",
+        installations.len(),
+        browser,
+        channel
+    );
+
+    Ok(installations.swap_remove(newest))
+}
+
+/// Find the launcher of the Flatpak application `app_id`, for the ids other than the ones
+/// published on Flathub.
+#[cfg(target_os = "linux")]
+pub fn find_flatpak_browser(browser: Browser, app_id: &str) -> Result<PathBuf, Error> {
+    find_first_existing(browser, flatpak::get_launcher_paths(app_id))
+}
+
+/// Flatpak is only available on Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn find_flatpak_browser(browser: Browser, _app_id: &str) -> Result<PathBuf, Error> {
+    find_first_existing(browser, Vec::new())
+}
+
+fn get_standard_paths(browser: Browser) -> Vec<PathBuf> {
+    get_standard_install_paths(browser)
+        .iter()
+        .map(PathBuf::from)
+        .chain(get_user_paths(browser))
+        .collect()
+}
+
+/// Per-user installations.
+#[cfg(target_os = "macos")]
+fn get_user_paths(browser: Browser) -> Vec<PathBuf> {
+    let relative_paths: &[&str] = match browser {
+        Browser::Chrome => &[
+            "Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+            "Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary",
+        ],
+        Browser::Edge => &["Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge"],
+        Browser::Firefox => &["Applications/Firefox.app/Contents/MacOS/firefox"],
+    };
+
+    join_all(std::env::var_os("HOME"), relative_paths)
+}
+
+/// Canary is only installed per user.
+#[cfg(target_os = "windows")]
+fn get_user_paths(browser: Browser) -> Vec<PathBuf> {
+    let relative_paths: &[&str] = match browser {
+        Browser::Chrome => &[
+            "Google\\Chrome\\Application\\chrome.exe",
+            "Google\\Chrome SxS\\Application\\chrome.exe",
+        ],
+        Browser::Edge => &[
+            "Microsoft\\Edge\\Application\\msedge.exe",
+            "Microsoft\\Edge SxS\\Application\\msedge.exe",
+        ],
+        Browser::Firefox => &["Mozilla Firefox\\firefox.exe"],
+    };
+
+    join_all(std::env::var_os("LOCALAPPDATA"), relative_paths)
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn join_all(directory: Option<std::ffi::OsString>, relative_paths: &[&str]) -> Vec<PathBuf> {
+    directory
+        .map(|directory| {
+            relative_paths
+                .iter()
+                .map(|relative_path| PathBuf::from(&directory).join(relative_path))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn get_user_paths(_browser: Browser) -> Vec<PathBuf> {
+    Vec::new()
+}
+
+fn find_first_existing(browser: Browser, candidates: Vec<PathBuf>) -> Result<PathBuf, Error> {
+    for candidate in &candidates {
+        debug!("Looking for {} at '{}'", browser, candidate.display());
+
+        if candidate.is_file() {
+            return Ok(candidate.clone());
+        }
+    }
+
+    Err(Error::BrowserNotFound(browser, candidates))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::browser_detection::{find_first_existing, select_browser};
+    use crate::{Browser, Channel, Error, Version};
+    use std::path::{Path, PathBuf};
+    use test_case::test_case;
+
+    #[test]
+    fn test_find_first_existing() {
+        let directory = tempfile::tempdir().unwrap();
+        let missing = directory.path().join("missing");
+        let existing = directory.path().join("chrome");
+
+        std::fs::write(&existing, "").unwrap();
+
+        let result = find_first_existing(
+            Browser::Chrome,
+            vec![missing, directory.path().to_path_buf(), existing.clone()],
+        );
+
+        assert_eq!(existing, result.unwrap());
+    }
+
+    #[test]
+    fn test_find_first_existing_lists_tried_paths() {
+        let directory = tempfile::tempdir().unwrap();
+        let candidates = vec![directory.path().join("a"), directory.path().join("b")];
+        let result = find_first_existing(Browser::Edge, candidates.clone());
+
+        assert!(
+            matches!(result, Err(Error::BrowserNotFound(Browser::Edge, tried)) if tried == candidates)
+        );
+    }
+
+    /// Create the installed files of `names` and return the candidates of all `names`.
+    fn make_installations(directory: &Path, names: &[&str], installed: &[&str]) -> Vec<PathBuf> {
+        for name in installed {
+            std::fs::write(directory.join(name), "").unwrap();
+        }
+
+        names.iter().map(|name| directory.join(name)).collect()
+    }
+
+    const NAMES: &[&str] = &[
+        "google-chrome",
+        "google-chrome-beta",
+        "google-chrome-unstable",
+        "google-chrome-canary",
+    ];
+
+    #[test_case(&["google-chrome", "google-chrome-beta"], Channel::Stable, "google-chrome" ; "stable first")]
+    #[test_case(&["google-chrome-canary", "google-chrome-beta", "google-chrome-unstable"], Channel::Stable, "google-chrome-beta" ; "beta then dev")]
+    #[test_case(&["google-chrome-canary", "google-chrome-unstable"], Channel::Stable, "google-chrome-unstable" ; "dev then canary")]
+    #[test_case(&["google-chrome", "google-chrome-beta"], Channel::Beta, "google-chrome-beta" ; "preferred channel")]
+    #[test_case(&["google-chrome-canary", "google-chrome-beta"], Channel::Dev, "google-chrome-beta" ; "preferred channel not installed")]
+    fn test_select_browser_channel(installed: &[&str], preferred_channel: Channel, expected: &str) {
+        let directory = tempfile::tempdir().unwrap();
+        let candidates = make_installations(directory.path(), NAMES, installed);
+        let result = select_browser(Browser::Chrome, candidates, preferred_channel, |_| None);
+
+        assert_eq!(directory.path().join(expected), result.unwrap());
+    }
+
+    #[test_case(&[Some(Version::new(119, 0, 6045, 105)), Some(Version::new(120, 0, 6099, 109))], "b" ; "newest")]
+    #[test_case(&[Some(Version::new(120, 0, 6099, 109)), Some(Version::new(120, 0, 6099, 109))], "a" ; "same version")]
+    #[test_case(&[None, Some(Version::new(119, 0, 6045, 105))], "b" ; "unknown version")]
+    #[test_case(&[None, None], "a" ; "unknown versions")]
+    fn test_select_browser_version(versions: &[Option<Version>], expected: &str) {
+        let directory = tempfile::tempdir().unwrap();
+
+        std::fs::create_dir(directory.path().join("a")).unwrap();
+        std::fs::create_dir(directory.path().join("b")).unwrap();
+
+        let names = ["a/google-chrome", "b/google-chrome"];
+        let candidates = make_installations(directory.path(), &names, &names);
+        let get_version = |path: &Path| {
+            let index = candidates.iter().position(|candidate| candidate == path);
+
+            versions[index.unwrap()]
+        };
+        let result = select_browser(
+            Browser::Chrome,
+            candidates.clone(),
+            Channel::Stable,
+            get_version,
+        );
+
+        assert_eq!(
+            directory.path().join(expected).join("google-chrome"),
+            result.unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_select_browser_follows_launchers() {
+        // Like /usr/bin/google-chrome when the beta is the default through the alternatives.
+        let directory = tempfile::tempdir().unwrap();
+        let candidates = make_installations(
+            directory.path(),
+            &["google-chrome", "google-chrome-beta"],
+            &["google-chrome-beta"],
+        );
+
+        std::os::unix::fs::symlink(&candidates[1], &candidates[0]).unwrap();
+
+        let result = select_browser(Browser::Chrome, candidates.clone(), Channel::Dev, |_| {
+            panic!("only one installation")
+        });
+
+        assert_eq!(candidates[0], result.unwrap());
+    }
+
+    #[test]
+    fn test_select_browser_not_found() {
+        let directory = tempfile::tempdir().unwrap();
+        let candidates = make_installations(directory.path(), NAMES, &[]);
+        let result = select_browser(Browser::Chrome, candidates.clone(), Channel::Stable, |_| {
+            None
+        });
+
+        assert!(
+            matches!(result, Err(Error::BrowserNotFound(Browser::Chrome, tried)) if tried == candidates)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Release channel of the browsers, the drivers of the channels other than stable can be
+//! published later than the browser.
+use crate::{Browser, Error};
+use log::warn;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum Channel {
+    #[default]
+    Stable,
+    Beta,
+    Dev,
+    Canary,
+}
+
+impl Channel {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+            Channel::Dev => "dev",
+            Channel::Canary => "canary",
+        }
+    }
+}
+
+/// Only the names of [`Channel::get_name`], unlike [`parse_channel`].
+impl FromStr for Channel {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "stable" => Ok(Channel::Stable),
+            "beta" => Ok(Channel::Beta),
+            "dev" => Ok(Channel::Dev),
+            "canary" => Ok(Channel::Canary),
+            _ => Err(Error::UnsupportedChannel(input.to_string())),
+        }
+    }
+}
+
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+/// Parse the channel from the branding of the browser, like `Google Chrome Beta`, the output of
+/// `google-chrome-beta --version` or the name of its installation directory.
+/// Example: `Google Chrome 121.0.6167.16 beta` or `Chrome SxS`
+pub fn parse_channel(branding: &str) -> Channel {
+    branding
+        .split(|character: char| !character.is_ascii_alphanumeric())
+        .find_map(|word| match word.to_ascii_lowercase().as_str() {
+            "beta" => Some(Channel::Beta),
+            "dev" | "unstable" => Some(Channel::Dev),
+            "canary" | "sxs" => Some(Channel::Canary),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// The channel found in the output of the browser or else in its location. Only the components
+/// of the location naming the browser are parsed, a directory like `/home/dev` is not a channel.
+pub fn get_browser_channel(program_path: &Path, version_output: &str) -> Channel {
+    let channel = parse_channel(version_output);
+
+    if channel != Channel::Stable {
+        return channel;
+    }
+
+    program_path
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .filter(|component| {
+            let component = component.to_ascii_lowercase();
+
+            ["chrome", "chromium", "edge"]
+                .iter()
+                .any(|name| component.contains(name))
+        })
+        .map(parse_channel)
+        .find(|channel| *channel != Channel::Stable)
+        .unwrap_or_default()
+}
+
+/// Chrome for Testing and the Edge driver storage can publish the driver of a beta, dev or canary
+/// build after the browser, in the meantime the closest driver may not match exactly.
+pub(crate) fn warn_if_not_stable(browser: Browser, program_path: &Path, version_output: &str) {
+    if browser == Browser::Firefox {
+        return;
+    }
+
+    let channel = get_browser_channel(program_path, version_output);
+
+    if channel != Channel::Stable {
+        warn!(
+            "{} {} detected, its driver may not be available yet or may lag behind the browser",
+            browser, channel
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::channel::{get_browser_channel, parse_channel, Channel};
+    use std::path::Path;
+    use test_case::test_case;
+
+    #[test_case("Google Chrome 120.0.6099.109 ", Channel::Stable ; "stable")]
+    #[test_case("Chromium 120.0.6099.109 built on Debian 12.4, running on Debian 12.4", Channel::Stable ; "chromium")]
+    #[test_case("Google Chrome 121.0.6167.16 beta", Channel::Beta ; "beta output")]
+    #[test_case("Google Chrome 122.0.6200.0 dev", Channel::Dev ; "dev output")]
+    #[test_case("Microsoft Edge 121.0.2277.4 beta", Channel::Beta ; "edge beta output")]
+    #[test_case("Google Chrome Beta", Channel::Beta ; "beta")]
+    #[test_case("Google Chrome Dev", Channel::Dev ; "dev")]
+    #[test_case("Google Chrome Canary", Channel::Canary ; "canary")]
+    #[test_case("google-chrome-unstable", Channel::Dev ; "linux dev package")]
+    #[test_case("Chrome SxS", Channel::Canary ; "windows canary directory")]
+    #[test_case("Developer Tools", Channel::Stable ; "partial word")]
+    fn test_parse_channel(branding: &str, expected: Channel) {
+        assert_eq!(expected, parse_channel(branding));
+    }
+
+    #[test_case("stable", Some(Channel::Stable) ; "stable")]
+    #[test_case("canary", Some(Channel::Canary) ; "canary")]
+    #[test_case("unstable", None ; "alias")]
+    fn test_from_str(input: &str, expected: Option<Channel>) {
+        assert_eq!(expected, input.parse().ok());
+    }
+
+    #[test_case("/usr/bin/google-chrome", "Google Chrome 120.0.6099.109 ", Channel::Stable ; "stable")]
+    #[test_case("/usr/bin/google-chrome-beta", "", Channel::Beta ; "from the location")]
+    #[test_case("/usr/bin/google-chrome", "Google Chrome 122.0.6200.0 dev", Channel::Dev ; "from the output")]
+    #[test_case("/home/dev/bin/google-chrome", "", Channel::Stable ; "unrelated directory")]
+    #[test_case("/Applications/Google Chrome Canary.app/Contents/MacOS/Google Chrome Canary", "", Channel::Canary ; "macos canary")]
+    #[test_case("C:/Users/user/AppData/Local/Google/Chrome SxS/Application/chrome.exe", "", Channel::Canary ; "windows canary")]
+    fn test_get_browser_channel(program_path: &str, version_output: &str, expected: Channel) {
+        assert_eq!(
+            expected,
+            get_browser_channel(Path::new(program_path), version_output)
+        );
+    }
+}
@@ -0,0 +1,463 @@
+//! Chrome for Testing JSON endpoints, the only source of chromedriver since Chrome 115.
+//! https://github.com/GoogleChromeLabs/chrome-for-testing#json-api-endpoints
+use crate::{Error, HttpClient, Platform, Version, DEFAULT_BASE_URL};
+use log::debug;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The first milestone whose chromedriver is published on Chrome for Testing.
+pub const FIRST_MILESTONE: u32 = 115;
+
+const JSON_BASE_URL: &str = "https://googlechromelabs.github.io/chrome-for-testing";
+
+const DOWNLOAD_BASE_URL: &str = "https://storage.googleapis.com/chrome-for-testing-public";
+
+pub(crate) const LATEST_VERSIONS_PER_MILESTONE_ENDPOINT: &str =
+    "latest-versions-per-milestone-with-downloads.json";
+
+const KNOWN_GOOD_VERSIONS_ENDPOINT: &str = "known-good-versions-with-downloads.json";
+
+/// A mirror replacing [`DEFAULT_BASE_URL`] serves Chrome for Testing at its root, next to the
+/// drivers published before it, like `<mirror>/known-good-versions-with-downloads.json` and
+/// `<mirror>/120.0.6099.109/linux64/chromedriver-linux64.zip`.
+fn get_mirror(base_url: &str) -> Option<&str> {
+    let base_url = base_url.trim_end_matches('/');
+
+    (base_url != DEFAULT_BASE_URL).then_some(base_url)
+}
+
+/// The URL of a JSON endpoint, on the mirror replacing [`DEFAULT_BASE_URL`] if any.
+pub(crate) fn get_endpoint_url(base_url: &str, endpoint: &str) -> String {
+    format!(
+        "{}/{}",
+        get_mirror(base_url).unwrap_or(JSON_BASE_URL),
+        endpoint
+    )
+}
+
+/// The URL the archives are under, the mirror replacing [`DEFAULT_BASE_URL`] if any.
+pub(crate) fn get_download_base_url(base_url: &str) -> &str {
+    get_mirror(base_url).unwrap_or(DOWNLOAD_BASE_URL)
+}
+
+/// Whether a mirror replaces [`DEFAULT_BASE_URL`], the URLs listed in the JSON endpoints are then
+/// the ones of storage.googleapis.com and its own are built from the layout instead.
+pub(crate) fn is_mirror(base_url: &str) -> bool {
+    get_mirror(base_url).is_some()
+}
+
+#[derive(Deserialize)]
+struct LatestVersionsPerMilestone {
+    milestones: HashMap<String, Release>,
+}
+
+#[derive(Deserialize)]
+struct KnownGoodVersions {
+    versions: Vec<Release>,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    version: String,
+    #[serde(default)]
+    downloads: Downloads,
+}
+
+/// Only the releases since Chrome 115 have a chromedriver.
+#[derive(Deserialize, Default)]
+struct Downloads {
+    #[serde(default)]
+    chromedriver: Vec<Download>,
+}
+
+#[derive(Deserialize)]
+struct Download {
+    platform: String,
+    url: String,
+}
+
+/// Deserialize a response, a truncated one or one whose layout changed is reported with the
+/// path of the field, like `milestones.120: missing field `version` at line 5 column 9`.
+fn parse_response<T: DeserializeOwned>(input: &str) -> Result<T, Error> {
+    let to_error = |path: String, error: serde_json::Error| {
+        Error::MalformedResponse(format!("{}: {}", path, error))
+    };
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    let response = serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|error| to_error(error.path().to_string(), error.into_inner()))?;
+
+    deserializer
+        .end()
+        .map_err(|error| to_error(String::from("."), error))?;
+
+    Ok(response)
+}
+
+/// The URL of the chromedriver of a platform in the downloads of a release, matched by the
+/// Chrome for Testing keys like `mac-x64`, which differ from the legacy ones like `mac64`.
+fn select_download_url<'a>(release: &'a Release, platform_key: &str) -> Option<&'a str> {
+    release
+        .downloads
+        .chromedriver
+        .iter()
+        .find(|download| download.platform == platform_key)
+        .map(|download| download.url.as_str())
+}
+
+/// The URL of the chromedriver of `version` for a platform, as listed by Chrome for Testing.
+/// The platform is a key of Chrome for Testing like `linux64`.
+pub(crate) fn get_listed_download_url(
+    client: &HttpClient,
+    version: Version,
+    platform_key: &str,
+) -> Result<String, Error> {
+    let response = client.get_text(&get_endpoint_url(
+        DEFAULT_BASE_URL,
+        KNOWN_GOOD_VERSIONS_ENDPOINT,
+    ))?;
+
+    parse_listed_download_url(&response, version, platform_key)
+}
+
+/// Find the URL of the chromedriver of `version` in the response of
+/// `known-good-versions-with-downloads.json`.
+pub(crate) fn parse_listed_download_url(
+    input: &str,
+    version: Version,
+    platform_key: &str,
+) -> Result<String, Error> {
+    let response: KnownGoodVersions = parse_response(input)?;
+
+    response
+        .versions
+        .iter()
+        .filter(|release| release.version.parse::<Version>().ok() == Some(version))
+        .find_map(|release| select_download_url(release, platform_key))
+        .map(String::from)
+        .ok_or(Error::NoDriverForVersion(version))
+}
+
+/// `base_url` is the one of [`crate::get_required_driver_version`].
+pub fn get_required_driver_version(
+    client: &HttpClient,
+    chrome_version: Version,
+    base_url: &str,
+) -> Result<Version, Error> {
+    let response = client.get_text(&get_endpoint_url(
+        base_url,
+        LATEST_VERSIONS_PER_MILESTONE_ENDPOINT,
+    ))?;
+
+    parse_latest_versions_per_milestone(&response, chrome_version)
+}
+
+/// Find the version of the latest build of the milestone of Chrome in the response of
+/// `latest-versions-per-milestone-with-downloads.json`.
+pub(crate) fn parse_latest_versions_per_milestone(
+    input: &str,
+    chrome_version: Version,
+) -> Result<Version, Error> {
+    let response: LatestVersionsPerMilestone = parse_response(input)?;
+    let entry = response
+        .milestones
+        .get(&chrome_version.major.to_string())
+        .ok_or_else(|| Error::NoDriverForVersion(chrome_version))?;
+
+    debug!(
+        "Latest version for milestone {}: {:?}",
+        chrome_version.major, entry.version
+    );
+
+    entry.version.parse()
+}
+
+/// The versions of chromedriver published for all the platforms, optionally only the ones of
+/// a milestone, from the oldest to the newest.
+pub fn get_available_driver_versions(
+    client: &HttpClient,
+    platforms: &[Platform],
+    milestone: Option<u32>,
+    base_url: &str,
+) -> Result<Vec<Version>, Error> {
+    let response = client.get_text(&get_endpoint_url(base_url, KNOWN_GOOD_VERSIONS_ENDPOINT))?;
+    let platform_keys = platforms
+        .iter()
+        .map(|platform| platform.get_chrome_for_testing_key())
+        .collect::<Vec<_>>();
+
+    parse_known_good_versions(&response, &platform_keys, milestone)
+}
+
+/// Find the versions with a chromedriver for all the platforms in the response of
+/// `known-good-versions-with-downloads.json`.
+pub(crate) fn parse_known_good_versions(
+    input: &str,
+    platform_keys: &[&str],
+    milestone: Option<u32>,
+) -> Result<Vec<Version>, Error> {
+    let response: KnownGoodVersions = parse_response(input)?;
+    let mut versions = Vec::new();
+
+    for release in response.versions {
+        let is_published = platform_keys
+            .iter()
+            .all(|platform_key| select_download_url(&release, platform_key).is_some());
+
+        if !is_published {
+            continue;
+        }
+
+        let version: Version = release.version.parse()?;
+
+        if milestone.is_some_and(|milestone| version.major != milestone) {
+            continue;
+        }
+
+        versions.push(version);
+    }
+
+    versions.sort();
+
+    Ok(versions)
+}
+
+/// Check `version` is a build of Chrome for Testing whose chromedriver is published for all
+/// the platforms, unlike a version given without looking. The platforms are the keys of
+/// Chrome for Testing like `linux64`, see [`Platform::get_chrome_for_testing_key`].
+pub fn get_exact_driver_version(
+    client: &HttpClient,
+    version: Version,
+    platform_keys: &[&str],
+    base_url: &str,
+) -> Result<Version, Error> {
+    let response = client.get_text(&get_endpoint_url(base_url, KNOWN_GOOD_VERSIONS_ENDPOINT))?;
+
+    parse_exact_version(&response, version, platform_keys)
+}
+
+/// Find `version` in the response of `known-good-versions-with-downloads.json`.
+pub(crate) fn parse_exact_version(
+    input: &str,
+    version: Version,
+    platform_keys: &[&str],
+) -> Result<Version, Error> {
+    parse_known_good_versions(input, platform_keys, Some(version.major))?
+        .contains(&version)
+        .then_some(version)
+        .ok_or(Error::NoSuchVersion(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chrome_for_testing::{
+        get_download_base_url, get_endpoint_url, parse_exact_version, parse_known_good_versions,
+        parse_latest_versions_per_milestone, parse_listed_download_url, select_download_url,
+        Release,
+    };
+    use crate::{Error, Platform, Version, DEFAULT_BASE_URL};
+    use test_case::test_case;
+
+    #[test_case(DEFAULT_BASE_URL, "https://googlechromelabs.github.io/chrome-for-testing/known-good-versions-with-downloads.json", "https://storage.googleapis.com/chrome-for-testing-public" ; "default")]
+    #[test_case("https://chromedriver.storage.googleapis.com/", "https://googlechromelabs.github.io/chrome-for-testing/known-good-versions-with-downloads.json", "https://storage.googleapis.com/chrome-for-testing-public" ; "default with slash")]
+    #[test_case("https://mirror.example.com/chromedriver/", "https://mirror.example.com/chromedriver/known-good-versions-with-downloads.json", "https://mirror.example.com/chromedriver" ; "mirror")]
+    fn test_get_urls(base_url: &str, expected_endpoint: &str, expected_download: &str) {
+        assert_eq!(
+            expected_endpoint,
+            get_endpoint_url(base_url, "known-good-versions-with-downloads.json")
+        );
+        assert_eq!(expected_download, get_download_base_url(base_url));
+    }
+
+    const LATEST_VERSIONS_PER_MILESTONE: &str = r#"{
+        "timestamp": "2023-12-19T08:09:22.704Z",
+        "milestones": {
+            "115": {
+                "milestone": "115",
+                "version": "115.0.5790.170",
+                "revision": "1148114",
+                "downloads": {}
+            },
+            "120": {
+                "milestone": "120",
+                "version": "120.0.6099.109",
+                "revision": "1217362",
+                "downloads": {}
+            }
+        }
+    }"#;
+
+    #[test_case(115, Some(Version::new(115, 0, 5790, 170)) ; "first milestone")]
+    #[test_case(120, Some(Version::new(120, 0, 6099, 109)) ; "basic")]
+    #[test_case(121, None ; "missing milestone")]
+    fn test_parse_latest_versions_per_milestone(milestone: u32, expected: Option<Version>) {
+        let chrome_version = Version::new(milestone, 0, 0, 0);
+        let result =
+            parse_latest_versions_per_milestone(LATEST_VERSIONS_PER_MILESTONE, chrome_version).ok();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test_case(&LATEST_VERSIONS_PER_MILESTONE[..200], "milestones.115.?: EOF while parsing a string at line 7 column 23" ; "truncated")]
+    #[test_case(r#"{"timestamp": "2023-12-19T08:09:22.704Z"}"#, ".: missing field `milestones` at line 1 column 41" ; "missing milestones")]
+    #[test_case(r#"{"milestones": {"120": {"milestone": "120"}}}"#, "milestones.120: missing field `version` at line 1 column 43" ; "missing version")]
+    #[test_case(r#"{"milestones": {"120": {"version": 120}}}"#, "milestones.120.version: invalid type: integer `120`, expected a string at line 1 column 38" ; "wrong type")]
+    #[test_case(r#"{"milestones": {}} garbage"#, ".: trailing characters at line 1 column 20" ; "garbled")]
+    fn test_parse_latest_versions_per_milestone_malformed(input: &str, expected: &str) {
+        let result = parse_latest_versions_per_milestone(input, Version::new(120, 0, 0, 0));
+
+        match result {
+            Err(Error::MalformedResponse(message)) => assert_eq!(expected, message),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_known_good_versions_malformed() {
+        let result = parse_known_good_versions(
+            r#"{"versions": [{"version": "120.0.6099.109", "downloads": {"chromedriver": [{"platform": "linux64"}]}}]}"#,
+            &["linux64"],
+            None,
+        );
+
+        assert!(
+            matches!(&result, Err(Error::MalformedResponse(message)) if message.starts_with("versions[0].downloads.chromedriver[0]: missing field `url`")),
+            "{:?}",
+            result
+        );
+    }
+
+    const RELEASE: &str = r#"{
+        "version": "120.0.6099.109",
+        "revision": "1217362",
+        "downloads": {
+            "chromedriver": [
+                {"platform": "linux64", "url": "https://example.com/linux64/chromedriver-linux64.zip"},
+                {"platform": "mac-arm64", "url": "https://example.com/mac-arm64/chromedriver-mac-arm64.zip"},
+                {"platform": "mac-x64", "url": "https://example.com/mac-x64/chromedriver-mac-x64.zip"},
+                {"platform": "win32", "url": "https://example.com/win32/chromedriver-win32.zip"},
+                {"platform": "win64", "url": "https://example.com/win64/chromedriver-win64.zip"}
+            ]
+        }
+    }"#;
+
+    #[test_case(Platform::Linux, Some("https://example.com/linux64/chromedriver-linux64.zip") ; "linux")]
+    #[test_case(Platform::LinuxArm64, None ; "linux arm64")]
+    #[test_case(Platform::MacOs, Some("https://example.com/mac-x64/chromedriver-mac-x64.zip") ; "macos")]
+    #[test_case(Platform::MacOsArm64, Some("https://example.com/mac-arm64/chromedriver-mac-arm64.zip") ; "macos arm64")]
+    #[test_case(Platform::Windows, Some("https://example.com/win32/chromedriver-win32.zip") ; "windows")]
+    #[test_case(Platform::Windows64, Some("https://example.com/win64/chromedriver-win64.zip") ; "windows 64")]
+    fn test_select_download_url(platform: Platform, expected: Option<&str>) {
+        let release: Release = serde_json::from_str(RELEASE).unwrap();
+
+        assert_eq!(
+            expected,
+            select_download_url(&release, platform.get_chrome_for_testing_key())
+        );
+    }
+
+    #[test]
+    fn test_select_download_url_missing() {
+        let release: Release =
+            serde_json::from_str(r#"{"version": "114.0.5735.90", "downloads": {}}"#).unwrap();
+
+        assert_eq!(None, select_download_url(&release, "linux64"));
+    }
+
+    fn get_keys(platforms: &[Platform]) -> Vec<&'static str> {
+        platforms
+            .iter()
+            .map(|platform| platform.get_chrome_for_testing_key())
+            .collect()
+    }
+
+    const KNOWN_GOOD_VERSIONS: &str = r#"{
+        "timestamp": "2023-12-19T08:09:22.704Z",
+        "versions": [
+            {
+                "version": "114.0.5735.90",
+                "revision": "1135570",
+                "downloads": {
+                    "chrome": [{"platform": "linux64", "url": "https://example.com/chrome-linux64.zip"}]
+                }
+            },
+            {
+                "version": "120.0.6099.109",
+                "revision": "1217362",
+                "downloads": {
+                    "chromedriver": [
+                        {"platform": "linux64", "url": "https://example.com/chromedriver-linux64.zip"},
+                        {"platform": "win64", "url": "https://example.com/chromedriver-win64.zip"}
+                    ]
+                }
+            },
+            {
+                "version": "120.0.6099.71",
+                "revision": "1217362",
+                "downloads": {
+                    "chromedriver": [
+                        {"platform": "linux64", "url": "https://example.com/chromedriver-linux64.zip"}
+                    ]
+                }
+            },
+            {
+                "version": "121.0.6167.16",
+                "revision": "1233107",
+                "downloads": {
+                    "chromedriver": [
+                        {"platform": "linux64", "url": "https://example.com/chromedriver-linux64.zip"}
+                    ]
+                }
+            }
+        ]
+    }"#;
+
+    #[test_case(&[Platform::Linux], None, &[Version::new(120, 0, 6099, 71), Version::new(120, 0, 6099, 109), Version::new(121, 0, 6167, 16)] ; "all milestones")]
+    #[test_case(&[Platform::Linux], Some(120), &[Version::new(120, 0, 6099, 71), Version::new(120, 0, 6099, 109)] ; "milestone")]
+    #[test_case(&[Platform::Linux, Platform::Windows64], None, &[Version::new(120, 0, 6099, 109)] ; "all platforms")]
+    #[test_case(&[Platform::Linux], Some(114), &[] ; "no chromedriver")]
+    fn test_parse_known_good_versions(
+        platforms: &[Platform],
+        milestone: Option<u32>,
+        expected: &[Version],
+    ) {
+        let result =
+            parse_known_good_versions(KNOWN_GOOD_VERSIONS, &get_keys(platforms), milestone)
+                .unwrap();
+
+        assert_eq!(expected, result);
+    }
+
+    #[test_case(Version::new(120, 0, 6099, 109), "win64", Some("https://example.com/chromedriver-win64.zip") ; "published")]
+    #[test_case(Version::new(120, 0, 6099, 71), "win64", None ; "missing platform")]
+    #[test_case(Version::new(120, 0, 6099, 62), "linux64", None ; "missing build")]
+    fn test_parse_listed_download_url(
+        version: Version,
+        platform_key: &str,
+        expected: Option<&str>,
+    ) {
+        let result = parse_listed_download_url(KNOWN_GOOD_VERSIONS, version, platform_key);
+
+        match expected {
+            Some(expected) => assert_eq!(expected, result.unwrap()),
+            None => assert!(
+                matches!(result, Err(Error::NoDriverForVersion(missing)) if missing == version)
+            ),
+        }
+    }
+
+    #[test_case(Version::new(120, 0, 6099, 71), &[Platform::Linux], true ; "published")]
+    #[test_case(Version::new(120, 0, 6099, 71), &[Platform::Linux, Platform::Windows64], false ; "missing platform")]
+    #[test_case(Version::new(120, 0, 6099, 62), &[Platform::Linux], false ; "missing build")]
+    #[test_case(Version::new(114, 0, 5735, 90), &[Platform::Linux], false ; "no chromedriver")]
+    fn test_parse_exact_version(version: Version, platforms: &[Platform], is_published: bool) {
+        let result = parse_exact_version(KNOWN_GOOD_VERSIONS, version, &get_keys(platforms));
+
+        match is_published {
+            true => assert_eq!(version, result.unwrap()),
+            false => {
+                assert!(matches!(result, Err(Error::NoSuchVersion(missing)) if missing == version))
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Removal of the files left in the output directory by the previous updates.
+use crate::{Browser, Error, Platform, Version};
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Remove the known artifacts of the driver from the output directory, keeping only the
+/// executable of the current platform. The other files are never touched.
+/// Returns the removed paths.
+pub fn clean_output_directory(
+    output_directory: &Path,
+    browser: Browser,
+    platform: Platform,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut removed = Vec::new();
+
+    for name in get_driver_artifacts(browser, platform) {
+        let path = output_directory.join(&name);
+
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else if path.is_file() {
+            fs::remove_file(&path)?;
+        } else {
+            continue;
+        }
+
+        info!("Removed '{}'", path.display());
+        removed.push(path);
+    }
+
+    Ok(removed)
+}
+
+/// True if the installed driver is of another major version than the required one, the files
+/// its archive left may then shadow the ones of the new driver.
+pub fn is_major_change(current_version: Option<Version>, required_version: Version) -> bool {
+    current_version.is_some_and(|current_version| current_version.major != required_version.major)
+}
+
+/// The names of the files and directories extracted from the driver archives, including the
+/// executables of the other platforms and the top-level directories of Chrome for Testing
+/// archives left by the versions not flattening them.
+fn get_driver_artifacts(browser: Browser, platform: Platform) -> Vec<String> {
+    let executable_name = browser.get_driver_executable_name(platform);
+    let mut artifacts = Platform::ALL
+        .iter()
+        .map(|other| browser.get_driver_executable_name(*other))
+        .filter(|name| *name != executable_name)
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    artifacts.dedup();
+
+    match browser {
+        Browser::Chrome => {
+            artifacts.push(String::from("LICENSE.chromedriver"));
+            artifacts.push(String::from("THIRD_PARTY_NOTICES.chromedriver"));
+            artifacts.extend(
+                Platform::ALL
+                    .iter()
+                    .map(|other| format!("chromedriver-{}", other.get_chrome_for_testing_key())),
+            );
+        }
+        Browser::Edge => artifacts.push(String::from("Driver_Notes")),
+        Browser::Firefox => {}
+    }
+
+    artifacts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cleanup::{clean_output_directory, is_major_change};
+    use crate::{Browser, Platform, Version};
+    use std::fs;
+    use test_case::test_case;
+
+    #[test_case(Some(Version::new(119, 0, 6045, 105)), Version::new(120, 0, 6099, 109), true ; "major bump")]
+    #[test_case(Some(Version::new(121, 0, 6167, 16)), Version::new(120, 0, 6099, 109), true ; "downgrade")]
+    #[test_case(Some(Version::new(120, 0, 6099, 71)), Version::new(120, 0, 6099, 109), false ; "same major")]
+    #[test_case(None, Version::new(120, 0, 6099, 109), false ; "not installed")]
+    fn test_is_major_change(
+        current_version: Option<Version>,
+        required_version: Version,
+        expected: bool,
+    ) {
+        assert_eq!(expected, is_major_change(current_version, required_version));
+    }
+
+    #[test]
+    fn test_clean_output_directory() {
+        let output_directory = tempfile::tempdir().unwrap();
+        let path = output_directory.path();
+
+        for file in [
+            "chromedriver",
+            "chromedriver.exe",
+            "LICENSE.chromedriver",
+            "THIRD_PARTY_NOTICES.chromedriver",
+            "notes.txt",
+            "geckodriver",
+        ] {
+            fs::write(path.join(file), "").unwrap();
+        }
+
+        fs::create_dir_all(path.join("chromedriver-linux64")).unwrap();
+        fs::write(path.join("chromedriver-linux64/chromedriver"), "").unwrap();
+        fs::create_dir(path.join("my-directory")).unwrap();
+
+        let removed = clean_output_directory(path, Browser::Chrome, Platform::Linux).unwrap();
+        let mut remaining = fs::read_dir(path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+
+        remaining.sort();
+
+        assert_eq!(4, removed.len());
+        assert_eq!(
+            vec!["chromedriver", "geckodriver", "my-directory", "notes.txt"],
+            remaining
+        );
+    }
+
+    #[test]
+    fn test_clean_output_directory_keeps_windows_executable() {
+        let output_directory = tempfile::tempdir().unwrap();
+        let path = output_directory.path();
+
+        fs::write(path.join("msedgedriver.exe"), "").unwrap();
+        fs::write(path.join("msedgedriver"), "").unwrap();
+        fs::create_dir(path.join("Driver_Notes")).unwrap();
+
+        clean_output_directory(path, Browser::Edge, Platform::Windows64).unwrap();
+
+        assert!(path.join("msedgedriver.exe").is_file());
+        assert!(!path.join("msedgedriver").exists());
+        assert!(!path.join("Driver_Notes").exists());
+    }
+}
@@ -0,0 +1,168 @@
+//! Defaults read from a configuration file, the values of the command line take precedence.
+use crate::Cli;
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use update_chrome_driver::{Browser, Platform};
+
+pub const FILE_NAME: &str = "update_chrome_driver.toml";
+
+/// The keys are named like the flags, e.g. `base-url = "https://mirror.example.com"`.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub chrome_browser_path: Option<PathBuf>,
+    pub output_directory: Option<PathBuf>,
+    pub browser: Option<Browser>,
+    pub clean: Option<bool>,
+    pub max_retries: Option<u32>,
+    pub timeout: Option<u64>,
+    pub proxy: Option<String>,
+    pub ca_cert: Option<PathBuf>,
+    pub user_agent: Option<String>,
+    pub base_url: Option<String>,
+    pub platform: Option<Platform>,
+}
+
+/// Load the first configuration file found in the current directory then in the
+/// configuration directory of the user. No file is the same as an empty file.
+pub fn load() -> Result<Config, String> {
+    let candidates = [
+        Some(PathBuf::from(FILE_NAME)),
+        directories::ProjectDirs::from("", "", "update_chrome_driver")
+            .map(|directories| directories.config_dir().join(FILE_NAME)),
+    ];
+
+    match candidates.into_iter().flatten().find(|path| path.is_file()) {
+        Some(path) => load_file(&path),
+        None => Ok(Config::default()),
+    }
+}
+
+fn load_file(path: &Path) -> Result<Config, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|error| format!("Can't read '{}': {}", path.display(), error))?;
+
+    parse(&content).map_err(|error| format!("Invalid '{}': {}", path.display(), error))
+}
+
+fn parse(content: &str) -> Result<Config, toml::de::Error> {
+    toml::from_str(content)
+}
+
+/// Replace the values of `cli` not specified on the command line or by an environment
+/// variable by the ones of the configuration.
+pub fn apply(config: Config, cli: &mut Cli, matches: &ArgMatches) {
+    let is_specified = |id: &str| {
+        matches!(
+            matches.value_source(id),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        )
+    };
+
+    if cli.chrome_browser_path.is_none() && cli.chrome_browser_path_option.is_none() {
+        cli.chrome_browser_path = config.chrome_browser_path;
+    }
+
+    if cli.output_directory.is_none() {
+        cli.output_directory = config.output_directory;
+    }
+
+    if let Some(browser) = config.browser.filter(|_| !is_specified("browser")) {
+        cli.browser = browser;
+    }
+
+    if let Some(clean) = config.clean.filter(|_| !is_specified("clean")) {
+        cli.clean = clean;
+    }
+
+    if let Some(max_retries) = config.max_retries.filter(|_| !is_specified("max_retries")) {
+        cli.max_retries = max_retries;
+    }
+
+    if let Some(timeout) = config.timeout.filter(|_| !is_specified("timeout")) {
+        cli.timeout = timeout;
+    }
+
+    if let Some(base_url) = config.base_url.filter(|_| !is_specified("base_url")) {
+        cli.base_url = base_url;
+    }
+
+    cli.proxy = cli.proxy.take().or(config.proxy);
+    cli.ca_cert = cli.ca_cert.take().or(config.ca_cert);
+    cli.user_agent = cli.user_agent.take().or(config.user_agent);
+    if cli.platform.is_empty() {
+        cli.platform.extend(config.platform);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{apply, parse};
+    use crate::Cli;
+    use clap::{CommandFactory, FromArgMatches};
+    use std::path::PathBuf;
+    use update_chrome_driver::{Browser, DEFAULT_BASE_URL};
+
+    const CONFIG: &str = r#"
+        output-directory = "drivers"
+        browser = "edge"
+        timeout = 60
+        base-url = "https://mirror.example.com"
+        proxy = "http://proxy:8080"
+        ca-cert = "ca.pem"
+    "#;
+
+    fn parse_cli(arguments: &[&str]) -> Cli {
+        let matches = Cli::command()
+            .try_get_matches_from(
+                std::iter::once("update_chrome_driver").chain(arguments.iter().copied()),
+            )
+            .unwrap();
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+
+        cli.resolve_positionals().unwrap();
+        apply(parse(CONFIG).unwrap(), &mut cli, &matches);
+        cli
+    }
+
+    #[test]
+    fn test_config_values_are_used_by_default() {
+        let cli = parse_cli(&[]);
+
+        assert_eq!(Some(PathBuf::from("drivers")), cli.output_directory);
+        assert_eq!(Browser::Edge, cli.browser);
+        assert_eq!(60, cli.timeout);
+        assert_eq!(3, cli.max_retries);
+        assert_eq!("https://mirror.example.com", cli.base_url);
+        assert_eq!(Some(String::from("http://proxy:8080")), cli.proxy);
+        assert_eq!(Some(PathBuf::from("ca.pem")), cli.ca_cert);
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let cli = parse_cli(&[
+            "--browser",
+            "chrome",
+            "--timeout",
+            "5",
+            "--base-url",
+            DEFAULT_BASE_URL,
+            "--proxy",
+            "http://other:3128",
+            "output",
+        ]);
+
+        assert_eq!(Some(PathBuf::from("output")), cli.output_directory);
+        assert_eq!(Browser::Chrome, cli.browser);
+        assert_eq!(5, cli.timeout);
+        assert_eq!(DEFAULT_BASE_URL, cli.base_url);
+        assert_eq!(Some(String::from("http://other:3128")), cli.proxy);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_keys() {
+        assert!(parse("output_directory = \"drivers\"").is_err());
+    }
+}
@@ -0,0 +1,28 @@
+//! Microsoft Edge WebDriver, a build is published for each version of Microsoft Edge.
+//! https://developer.microsoft.com/en-us/microsoft-edge/tools/webdriver/
+use crate::{Platform, Version};
+
+pub fn get_download_url(required_version: Version, platform: Platform) -> String {
+    format!(
+        "https://msedgedriver.azureedge.net/{}/edgedriver_{}.zip",
+        required_version,
+        platform.get_edge_key()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edge::get_download_url;
+    use crate::{Platform, Version};
+    use test_case::test_case;
+
+    #[test_case(Platform::Windows64, "https://msedgedriver.azureedge.net/120.0.2210.91/edgedriver_win64.zip" ; "windows")]
+    #[test_case(Platform::MacOsArm64, "https://msedgedriver.azureedge.net/120.0.2210.91/edgedriver_mac64_m1.zip" ; "macos apple silicon")]
+    #[test_case(Platform::Linux, "https://msedgedriver.azureedge.net/120.0.2210.91/edgedriver_linux64.zip" ; "linux")]
+    fn test_get_download_url(platform: Platform, expected: &str) {
+        assert_eq!(
+            expected,
+            get_download_url(Version::new(120, 0, 2210, 91), platform)
+        );
+    }
+}
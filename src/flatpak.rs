@@ -0,0 +1,107 @@
+//! Browsers installed with Flatpak are not in the PATH, but Flatpak exports a launcher running
+//! `flatpak run <app id>` for each application, in the `exports/bin` directory of the system
+//! installation and of the installation of the user.
+use crate::{parsers, run_program, Browser, Error, Version};
+use std::path::{Path, PathBuf};
+
+const SYSTEM_EXPORTS_DIRECTORY: &str = "/var/lib/flatpak/exports/bin";
+
+/// The ids of the applications published on Flathub.
+pub fn get_default_app_ids(browser: Browser) -> &'static [&'static str] {
+    match browser {
+        Browser::Chrome => &["com.google.Chrome", "org.chromium.Chromium"],
+        Browser::Edge => &["com.microsoft.Edge"],
+        Browser::Firefox => &["org.mozilla.firefox"],
+    }
+}
+
+pub fn get_launcher_paths(app_id: &str) -> Vec<PathBuf> {
+    let user_data_directory = std::env::var_os("XDG_DATA_HOME")
+        .filter(|directory| !directory.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+
+    std::iter::once(PathBuf::from(SYSTEM_EXPORTS_DIRECTORY))
+        .chain(user_data_directory.map(|directory| directory.join("flatpak/exports/bin")))
+        .map(|directory| directory.join(app_id))
+        .collect()
+}
+
+/// The id of the application of a launcher, `None` for the other programs.
+pub fn get_app_id(program_path: &Path) -> Option<&str> {
+    program_path
+        .parent()
+        .filter(|directory| directory.ends_with("flatpak/exports/bin"))
+        .and_then(|_| program_path.file_name()?.to_str())
+}
+
+/// Read with `flatpak info`, which is faster than `flatpak run` and does not start the sandbox.
+pub fn get_version(app_id: &str) -> Result<Version, Error> {
+    let flatpak = which::which("flatpak")
+        .map_err(|_| Error::ProgramDoesNotExist(PathBuf::from("flatpak")))?;
+    let stdout = run_program(&flatpak, ["info", app_id])?;
+
+    parse_info(&stdout)
+}
+
+fn parse_info(output: &str) -> Result<Version, Error> {
+    output
+        .lines()
+        .find_map(|line| parsers::parse_flatpak_info_version(line).ok())
+        .map(|(_, version)| version)
+        .ok_or_else(|| {
+            Error::ParsingVersionFailed(String::from("flatpak info did not print a version"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flatpak::{get_app_id, get_launcher_paths, parse_info};
+    use crate::Version;
+    use std::path::{Path, PathBuf};
+    use test_case::test_case;
+
+    const CHROME_INFO: &str = "
+Google Chrome - The browser built to be yours
+
+          ID: com.google.Chrome
+         Ref: app/com.google.Chrome/x86_64/stable
+        Arch: x86_64
+      Branch: stable
+     Version: 120.0.6099.109-1
+     License: LicenseRef-proprietary
+      Origin: flathub
+  Collection: org.flathub.Stable
+Installation: system
+   Installed: 356.9 MB
+     Runtime: org.freedesktop.Platform/x86_64/23.08
+         Sdk: org.freedesktop.Sdk/x86_64/23.08
+";
+
+    #[test_case(CHROME_INFO, Some(Version::new(120, 0, 6099, 109)) ; "chrome")]
+    #[test_case("     Version: 121.0.1\n", Some(Version::new(121, 0, 1, 0)) ; "firefox")]
+    #[test_case("          ID: com.example.App\n", None ; "no version")]
+    fn test_parse_info(output: &str, expected: Option<Version>) {
+        assert_eq!(expected, parse_info(output).ok());
+    }
+
+    #[test_case("/var/lib/flatpak/exports/bin/com.google.Chrome", Some("com.google.Chrome") ; "system")]
+    #[test_case("/home/user/.local/share/flatpak/exports/bin/org.chromium.Chromium", Some("org.chromium.Chromium") ; "user")]
+    #[test_case("/usr/bin/google-chrome", None ; "native")]
+    fn test_get_app_id(program_path: &str, expected: Option<&str>) {
+        assert_eq!(expected, get_app_id(Path::new(program_path)));
+    }
+
+    #[test]
+    fn test_get_launcher_paths() {
+        let paths = get_launcher_paths("com.google.Chrome");
+
+        assert_eq!(
+            PathBuf::from("/var/lib/flatpak/exports/bin/com.google.Chrome"),
+            paths[0]
+        );
+        assert!(paths
+            .iter()
+            .all(|path| get_app_id(path) == Some("com.google.Chrome")));
+    }
+}
@@ -0,0 +1,145 @@
+//! geckodriver releases, published on GitHub with a versioning independent of Firefox.
+//! https://firefox-source-docs.mozilla.org/testing/geckodriver/Support.html
+use crate::{parsers, Error, HttpClient, Platform, Version};
+use log::debug;
+use nom::combinator::all_consuming;
+use nom::Finish;
+use serde::Deserialize;
+
+pub(crate) const RELEASES_URL: &str = "https://api.github.com/repos/mozilla/geckodriver/releases";
+
+/// The minimum version of Firefox supported by each geckodriver release, from the newest
+/// to the oldest. A release newer than the first entry requires at least its Firefox version.
+const MINIMUM_FIREFOX_VERSIONS: &[((u32, u32, u32), u32)] = &[
+    ((0, 36, 0), 128),
+    ((0, 34, 0), 115),
+    ((0, 32, 0), 102),
+    ((0, 31, 0), 91),
+    ((0, 30, 0), 78),
+    ((0, 26, 0), 60),
+    ((0, 21, 0), 57),
+    ((0, 20, 1), 55),
+    ((0, 18, 0), 53),
+    ((0, 17, 0), 52),
+];
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+pub fn get_required_driver_version(
+    client: &HttpClient,
+    firefox_version: Version,
+) -> Result<Version, Error> {
+    let response = client.get_text(RELEASES_URL)?;
+
+    parse_releases(&response, firefox_version)
+}
+
+/// The archives are zip files on Windows and gzipped tarballs elsewhere.
+pub fn get_download_url(required_version: Version, platform: Platform) -> String {
+    let extension = match platform {
+        Platform::Windows | Platform::Windows64 => "zip",
+        _ => "tar.gz",
+    };
+
+    format!(
+        "https://github.com/mozilla/geckodriver/releases/download/v{0}/geckodriver-v{0}-{1}.{2}",
+        format_release_version(required_version),
+        platform.get_geckodriver_key(),
+        extension
+    )
+}
+
+/// geckodriver versions have three components, like `0.34.0`.
+fn format_release_version(version: Version) -> String {
+    format!("{}.{}.{}", version.major, version.minor, version.build)
+}
+
+/// Find the newest published release supporting the version of Firefox in the response
+/// of the GitHub releases API.
+pub(crate) fn parse_releases(input: &str, firefox_version: Version) -> Result<Version, Error> {
+    let releases: Vec<Release> = serde_json::from_str(input)?;
+
+    releases
+        .iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .filter_map(|release| parse_tag_name(&release.tag_name))
+        .filter(|version| is_compatible(*version, firefox_version))
+        .max()
+        .inspect(|version| {
+            debug!(
+                "Latest geckodriver for Firefox {}: {}",
+                firefox_version,
+                format_release_version(*version)
+            )
+        })
+        .ok_or_else(|| Error::NoDriverForVersion(firefox_version))
+}
+
+/// Tags are named like `v0.34.0`.
+fn parse_tag_name(tag_name: &str) -> Option<Version> {
+    let version = tag_name.strip_prefix('v')?;
+
+    all_consuming(parsers::parse_release_version_numbers)(version)
+        .finish()
+        .ok()
+        .map(|(_, version)| version)
+}
+
+/// Releases older than the known ones are considered incompatible.
+pub fn is_compatible(geckodriver_version: Version, firefox_version: Version) -> bool {
+    get_minimum_firefox_version(geckodriver_version)
+        .is_some_and(|minimum| minimum <= firefox_version.major)
+}
+
+fn get_minimum_firefox_version(geckodriver_version: Version) -> Option<u32> {
+    let version = (
+        geckodriver_version.major,
+        geckodriver_version.minor,
+        geckodriver_version.build,
+    );
+
+    MINIMUM_FIREFOX_VERSIONS
+        .iter()
+        .find(|(release, _)| *release <= version)
+        .map(|(_, minimum)| *minimum)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geckodriver::{get_download_url, parse_releases};
+    use crate::{Platform, Version};
+    use test_case::test_case;
+
+    const RELEASES: &str = r#"[
+        {"tag_name": "v0.36.0", "draft": false, "prerelease": false, "assets": []},
+        {"tag_name": "v0.35.0", "draft": false, "prerelease": false, "assets": []},
+        {"tag_name": "v0.34.1", "draft": false, "prerelease": true, "assets": []},
+        {"tag_name": "v0.33.0", "draft": false, "prerelease": false, "assets": []},
+        {"tag_name": "v0.31.0", "draft": false, "prerelease": false, "assets": []}
+    ]"#;
+
+    #[test_case(Version::new(130, 0, 1, 0), Some(Version::new(0, 36, 0, 0)) ; "latest")]
+    #[test_case(Version::new(115, 5, 0, 0), Some(Version::new(0, 35, 0, 0)) ; "extended support release")]
+    #[test_case(Version::new(110, 0, 0, 0), Some(Version::new(0, 33, 0, 0)) ; "older firefox")]
+    #[test_case(Version::new(80, 0, 0, 0), None ; "too old")]
+    fn test_parse_releases(firefox_version: Version, expected: Option<Version>) {
+        assert_eq!(expected, parse_releases(RELEASES, firefox_version).ok());
+    }
+
+    #[test_case(Platform::Linux, "https://github.com/mozilla/geckodriver/releases/download/v0.34.0/geckodriver-v0.34.0-linux64.tar.gz" ; "linux")]
+    #[test_case(Platform::MacOsArm64, "https://github.com/mozilla/geckodriver/releases/download/v0.34.0/geckodriver-v0.34.0-macos-aarch64.tar.gz" ; "macos apple silicon")]
+    #[test_case(Platform::Windows64, "https://github.com/mozilla/geckodriver/releases/download/v0.34.0/geckodriver-v0.34.0-win64.zip" ; "windows")]
+    fn test_get_download_url(platform: Platform, expected: &str) {
+        assert_eq!(
+            expected,
+            get_download_url(Version::new(0, 34, 0, 0), platform)
+        );
+    }
+}
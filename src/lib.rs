@@ -0,0 +1,2897 @@
+//! Downloads the correct version of chromedriver regarding the version of the local Chrome.
+//! Basically the rust implementation of https://chromedriver.chromium.org/downloads/version-selection.
+use log::{debug, info, warn};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+#[cfg(any(target_os = "macos", test))]
+mod app_bundle;
+mod browser;
+mod browser_detection;
+mod channel;
+mod chrome_for_testing;
+mod cleanup;
+mod edge;
+#[cfg(target_os = "linux")]
+mod flatpak;
+mod geckodriver;
+mod http;
+mod link;
+mod lock;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+mod parsers;
+mod platform;
+mod process;
+mod progress;
+mod storage_listing;
+#[cfg(test)]
+mod test_server;
+mod timings;
+mod version;
+pub mod versions;
+
+pub use browser::Browser;
+pub use browser_detection::{find_browser, find_flatpak_browser};
+pub use channel::{get_browser_channel, parse_channel, Channel};
+pub use chrome_for_testing::{get_available_driver_versions, get_exact_driver_version};
+pub use cleanup::{clean_output_directory, is_major_change};
+pub use http::{HttpClient, HttpOptions, DEFAULT_USER_AGENT};
+pub use link::link_driver;
+pub use lock::{lock_output_directory, OutputDirectoryLock, DEFAULT_LOCK_TIMEOUT};
+pub use platform::Platform;
+pub use process::set_program_timeout;
+pub use timings::Timings;
+pub use version::Version;
+
+/// Where the drivers were published before Chrome for Testing, a mirror with the same layout
+/// can be used instead.
+pub const DEFAULT_BASE_URL: &str = "https://chromedriver.storage.googleapis.com";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Program '{0}' does not exist")]
+    ProgramDoesNotExist(PathBuf),
+
+    #[error("Can't run '{0}': {1}{}", describe_spawn_error(.0, .1))]
+    CantRunProgram(PathBuf, #[source] std::io::Error),
+
+    #[error("'{0}' did not exit in time and was killed")]
+    ProgramTimedOut(PathBuf),
+
+    #[error("'{0}' printed nothing when asked its version, on stdout or stderr")]
+    EmptyVersionOutput(PathBuf),
+
+    #[error("Can't read the version file '{0}': {1}")]
+    CantReadVersionFile(PathBuf, #[source] std::io::Error),
+
+    #[error("Failed to read output: {0}")]
+    FailedToReadOutput(#[from] std::io::Error),
+
+    #[error("Failed to parse version: {0}")]
+    ParsingVersionFailed(String),
+
+    #[error("Network error: {0}")]
+    Network(#[source] reqwest::Error),
+
+    #[error("Request timed out: {0}")]
+    Timeout(#[source] reqwest::Error),
+
+    #[error("TLS error, the certificate of the server may not be trusted: {0}")]
+    Tls(#[source] reqwest::Error),
+
+    #[error("HTTP status {0}: {1}")]
+    HttpStatus(u16, #[source] reqwest::Error),
+
+    #[error("Can't use '{0}' as CA certificate: {1}")]
+    InvalidCaCertificate(PathBuf, String),
+
+    #[error(transparent)]
+    ZipExtractionFailed(#[from] zip::result::ZipError),
+
+    #[error("Failed to extract tarball: {0}")]
+    TarExtractionFailed(#[source] std::io::Error),
+
+    #[error("Failed to parse JSON response: {0}")]
+    JsonParsingFailed(#[from] serde_json::Error),
+
+    #[error("The response of Chrome for Testing has an unexpected layout, at {0}")]
+    MalformedResponse(String),
+
+    #[error("Failed to parse XML response: {0}")]
+    XmlParsingFailed(#[from] quick_xml::Error),
+
+    #[error("No driver available for browser version {0}, it may not be published yet: try again later or use --chrome-version to get the driver of an older version")]
+    NoDriverForVersion(Version),
+
+    #[error("{0} not found, use --browser-path to specify its location. Tried:\n{}", format_paths(.1))]
+    BrowserNotFound(Browser, Vec<PathBuf>),
+
+    #[error("{0} is not installed at '{1}'. Fix the location given with --browser-path, or on the command line or in CHROME_BIN for chrome, or remove it to search the standard install locations")]
+    BrowserNotInstalled(Browser, PathBuf),
+
+    #[error("'{0}' is a directory, not the executable of the browser")]
+    NotAnExecutable(PathBuf),
+
+    #[error("Unsupported platform '{0}'")]
+    UnsupportedPlatform(String),
+
+    #[error("Unsupported browser '{0}'")]
+    UnsupportedBrowser(String),
+
+    #[error("Unsupported match policy '{0}', expected exact or major")]
+    UnsupportedMatchPolicy(String),
+
+    #[error("Unsupported channel '{0}', expected stable, beta, dev or canary")]
+    UnsupportedChannel(String),
+
+    #[error("Checksum mismatch: expected SHA-256 {0}, got {1}")]
+    ChecksumMismatch(String, String),
+
+    #[error("The installed driver is not working: {0}")]
+    DriverVerificationFailed(String),
+
+    #[error("The driver '{0}' failed to start, {1}")]
+    SmokeTestFailed(PathBuf, String),
+
+    #[error("Can't use '{0}' as output directory: {1}")]
+    InvalidOutputDirectory(PathBuf, #[source] std::io::Error),
+
+    #[error("Can't write in the output directory '{0}': {1}")]
+    OutputDirectoryNotWritable(PathBuf, #[source] std::io::Error),
+
+    #[error("Can't link the driver to '{0}': {1}")]
+    LinkFailed(PathBuf, #[source] std::io::Error),
+
+    #[error("The download is larger than the maximum of {0} bytes")]
+    DownloadTooLarge(u64),
+
+    #[error("The download is not an archive, a proxy or a captive portal may have replaced it. Received: {0}")]
+    UnexpectedContent(String),
+
+    #[error("The response of {0} is empty, a proxy may have truncated it")]
+    EmptyResponse(String),
+
+    #[error("The download is incomplete, {1} bytes were received out of {0}")]
+    IncompleteDownload(u64, u64),
+
+    #[error("Another update of '{0}' is still running after {1:?}")]
+    LockTimeout(PathBuf, Duration),
+
+    #[error("chromedriver {0} is not published on Chrome for Testing for this platform")]
+    NoSuchVersion(Version),
+
+    #[error("The post-install command '{0}' failed: {1}")]
+    PostInstallFailed(String, String),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        http::classify_error(error)
+    }
+}
+
+fn format_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("  {}", path.display()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Why a program that can't be spawned is not found or not permitted is not obvious, e.g. an
+/// existing file is reported not found when its interpreter or its loader is missing.
+fn describe_spawn_error(program_path: &Path, error: &std::io::Error) -> &'static str {
+    let exists = program_path.exists();
+
+    match error.kind() {
+        ErrorKind::NotFound if exists => {
+            " (the file exists, its interpreter or a library it requires may be missing)"
+        }
+        ErrorKind::NotFound if program_path.components().count() == 1 => " (not found in the PATH)",
+        ErrorKind::NotFound => " (the file does not exist)",
+        ErrorKind::PermissionDenied if exists => " (check the file is executable)",
+        _ => "",
+    }
+}
+
+impl Error {
+    /// True if the error is a HTTP 404 response, e.g. when no driver is published for a version.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::HttpStatus(404, _))
+    }
+}
+
+/// When the installed driver is considered up to date, see [`must_update`].
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum MatchPolicy {
+    /// Updated to any newer driver, even for a patch.
+    #[default]
+    Exact,
+    /// Updated only when its major version differs, the drivers are compatible with any
+    /// browser of the same major version.
+    Major,
+}
+
+impl FromStr for MatchPolicy {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "exact" => Ok(MatchPolicy::Exact),
+            "major" => Ok(MatchPolicy::Major),
+            _ => Err(Error::UnsupportedMatchPolicy(input.to_string())),
+        }
+    }
+}
+
+pub fn must_update(
+    current_version: Option<Version>,
+    new_version: Version,
+    policy: MatchPolicy,
+) -> bool {
+    current_version.is_none_or(|current_version| match policy {
+        MatchPolicy::Exact => current_version < new_version,
+        MatchPolicy::Major => current_version.major != new_version.major,
+    })
+}
+
+/// Whether a driver supports a version of the browser without querying the network.
+/// chromedriver and msedgedriver are released with the browser, their major version must
+/// match, geckodriver is compatible with the versions of Firefox newer than its minimum.
+pub fn is_driver_compatible(
+    browser: Browser,
+    browser_version: Version,
+    driver_version: Version,
+) -> bool {
+    match browser {
+        Browser::Chrome | Browser::Edge => browser_version.major == driver_version.major,
+        Browser::Firefox => geckodriver::is_compatible(driver_version, browser_version),
+    }
+}
+
+/// Options of [`download_and_extract`].
+#[derive(Debug, Default)]
+pub struct DownloadOptions<'a> {
+    /// The archive is extracted only if its SHA-256 matches.
+    pub expected_sha256: Option<&'a str>,
+
+    /// Draw the progress of the download on stderr.
+    pub show_progress: bool,
+
+    /// The extracted driver is run and must report this version before replacing the installed one.
+    pub verify_version: Option<Version>,
+
+    /// The name the driver is installed with instead of its standard one, like `chromedriver_2`.
+    pub driver_name: Option<&'a str>,
+
+    /// The download is aborted if the archive is larger, as advertised by its `Content-Length`
+    /// or once more bytes were received. Unlimited if `None`.
+    pub max_download_size: Option<u64>,
+
+    /// Drawn before the progress bar, to tell apart the concurrent downloads.
+    pub progress_label: Option<&'a str>,
+
+    /// Where the durations of the download, the extraction and the verification are added.
+    pub timings: Option<&'a Timings>,
+}
+
+/// The archive is streamed to a temporary file, so the memory usage does not depend on its size.
+/// Gzipped tarballs are recognized by the extension of the URL or by their content, the other
+/// archives are extracted as zip archives. The output directory is created if needed.
+pub fn download_and_extract(
+    client: &HttpClient,
+    url: &str,
+    output_directory: &Path,
+    browser: Browser,
+    platform: Platform,
+    options: &DownloadOptions,
+) -> Result<(), Error> {
+    check_output_directory(output_directory)?;
+
+    let mut file = tempfile::tempfile()?;
+
+    download_to_file(client, url, &mut file, options)?;
+    extract_downloaded_archive(file, url, output_directory, browser, platform, options)
+}
+
+/// A file or a directory the extraction of an archive would write in the output directory.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct PlannedFile {
+    /// Its name in the output directory, the driver is named after
+    /// [`DownloadOptions::driver_name`].
+    pub name: String,
+
+    /// True if it replaces a file of the output directory, false if it is created.
+    pub overwrite: bool,
+}
+
+/// Download the archive like [`download_and_extract`] and list what its extraction would
+/// write in the output directory, from the list of its entries without extracting them.
+/// Nothing is written in the output directory.
+pub fn preview_extraction(
+    client: &HttpClient,
+    url: &str,
+    output_directory: &Path,
+    browser: Browser,
+    platform: Platform,
+    options: &DownloadOptions,
+) -> Result<Vec<PlannedFile>, Error> {
+    let mut file = tempfile::tempfile()?;
+
+    download_to_file(client, url, &mut file, options)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let is_tar_gz = is_tar_gz(url, &mut file)?;
+
+    check_archive_content(&mut file, if is_tar_gz { &GZIP_MAGIC } else { &ZIP_MAGIC })?;
+
+    if let Some(expected_sha256) = options.expected_sha256 {
+        verify_checksum(&mut file, expected_sha256)?;
+        file.seek(SeekFrom::Start(0))?;
+    }
+
+    let entries = if is_tar_gz {
+        list_tar_gz(file)?
+    } else {
+        list_archive(file)?
+    };
+    let names = get_installed_names(&entries);
+    let executable_name = browser.get_driver_executable_name(platform);
+    // The driver found under one of its names like it is by the extraction.
+    let driver = browser
+        .get_driver_executable_names(platform)
+        .into_iter()
+        .find(|name| names.contains(name));
+
+    Ok(names
+        .into_iter()
+        .map(|name| {
+            let name = match options.driver_name {
+                _ if Some(&name) != driver.as_ref() => name,
+                Some(driver_name) => driver_name.to_string(),
+                None => executable_name.to_string(),
+            };
+
+            PlannedFile {
+                overwrite: output_directory.join(&name).exists(),
+                name,
+            }
+        })
+        .collect())
+}
+
+/// The paths of the entries of a zip archive, read from its central directory. Like the
+/// extraction, the entries whose path leaves the archive are skipped.
+fn list_archive<R: Read + Seek>(reader: R) -> Result<Vec<PathBuf>, Error> {
+    let mut archive = zip::read::ZipArchive::new(reader)?;
+    let mut paths = Vec::new();
+
+    for index in 0..archive.len() {
+        if let Some(path) = archive.by_index_raw(index)?.enclosed_name() {
+            paths.push(path.to_path_buf());
+        }
+    }
+
+    Ok(paths)
+}
+
+/// The paths of the entries of a gzipped tarball, without the ones leaving the archive.
+fn list_tar_gz<R: Read>(reader: R) -> Result<Vec<PathBuf>, Error> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(reader));
+    let mut paths = Vec::new();
+
+    for entry in archive.entries().map_err(Error::TarExtractionFailed)? {
+        let path = entry
+            .map_err(Error::TarExtractionFailed)?
+            .path()
+            .map_err(Error::TarExtractionFailed)?
+            .into_owned();
+
+        if path
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_)))
+        {
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+/// The names moved to the output directory by [`install_extracted_files`], the top-level
+/// entries of the archive, or the ones of its single top-level directory which is flattened.
+fn get_installed_names(paths: &[PathBuf]) -> Vec<String> {
+    let get_name = |path: &Path, depth: usize| {
+        path.components()
+            .nth(depth)
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+    };
+    let roots = paths
+        .iter()
+        .filter_map(|path| get_name(path, 0))
+        .collect::<std::collections::BTreeSet<_>>();
+    let is_directory = |root: &String| {
+        paths
+            .iter()
+            .any(|path| path.components().count() > 1 && get_name(path, 0).as_ref() == Some(root))
+    };
+    let depth = match roots.iter().collect::<Vec<_>>().as_slice() {
+        [root] if is_directory(root) => 1,
+        _ => 0,
+    };
+
+    paths
+        .iter()
+        .filter_map(|path| get_name(path, depth))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Download the archive to `archive_path` without extracting it, like [`download_and_extract`]
+/// streamed to a temporary file next to it, which replaces it only once its content and its
+/// checksum are checked. Returns the size of the archive.
+pub fn download_archive(
+    client: &HttpClient,
+    url: &str,
+    archive_path: &Path,
+    options: &DownloadOptions,
+) -> Result<u64, Error> {
+    let directory = match archive_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    create_output_directory(directory)?;
+
+    let mut file = tempfile::NamedTempFile::new_in(directory)?;
+    let size = download_to_file(client, url, file.as_file_mut(), options)?;
+
+    file.seek(SeekFrom::Start(0))?;
+
+    let magic: &[u8] = match is_tar_gz(url, file.as_file_mut())? {
+        true => &GZIP_MAGIC,
+        false => &ZIP_MAGIC,
+    };
+
+    check_archive_content(file.as_file_mut(), magic)?;
+
+    if let Some(expected_sha256) = options.expected_sha256 {
+        verify_checksum(file.as_file_mut(), expected_sha256)?;
+    }
+
+    file.persist(archive_path).map_err(|error| error.error)?;
+
+    let persisted_size = fs::metadata(archive_path)?.len();
+
+    if persisted_size != size {
+        return Err(Error::IncompleteDownload(size, persisted_size));
+    }
+
+    Ok(size)
+}
+
+/// When reading the body is interrupted, the download is resumed with a range request if the
+/// server accepts them, or restarted from the beginning if not or if the download changed.
+/// Returns the number of bytes written, which must be the `Content-Length` when known.
+fn download_to_file(
+    client: &HttpClient,
+    url: &str,
+    file: &mut fs::File,
+    options: &DownloadOptions,
+) -> Result<u64, Error> {
+    let start = std::time::Instant::now();
+    let mut response = client.get(url)?;
+    let mut expected_size = response.content_length();
+
+    check_download_size(expected_size, options.max_download_size)?;
+
+    let progress_bar = progress::make_progress_bar(
+        response.content_length(),
+        options.show_progress,
+        options.progress_label,
+    );
+    let mut resume_validator = http::get_resume_validator(response.headers());
+    let mut written = 0;
+    let mut attempt = 0;
+
+    loop {
+        let mut writer = SizeLimitedWriter {
+            inner: progress_bar.wrap_write(&mut *file),
+            written,
+            max_size: options.max_download_size,
+        };
+        let result = response.copy_to(&mut writer);
+
+        written = writer.written;
+
+        let error = match result {
+            Ok(_) => break,
+            Err(error) => {
+                check_download_size(Some(written), options.max_download_size)?;
+
+                if attempt >= client.max_retries() || !http::is_interrupted(&error) {
+                    return Err(error.into());
+                }
+
+                error
+            }
+        };
+        let delay = client.retry_delay(attempt);
+
+        attempt += 1;
+        response = match &resume_validator {
+            Some(etag) => {
+                warn!("{}, resuming at {} bytes in {:?}", error, written, delay);
+                std::thread::sleep(delay);
+                client.get_range(url, written, etag.as_deref())?
+            }
+            None => {
+                warn!("{}, restarting in {:?}", error, delay);
+                std::thread::sleep(delay);
+                client.get(url)?
+            }
+        };
+
+        if !http::is_resumed_at(response.status(), response.headers(), written) {
+            debug!("Downloading {} again from the beginning", url);
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            progress_bar.set_position(0);
+            resume_validator = http::get_resume_validator(response.headers());
+            expected_size = response.content_length();
+            written = 0;
+        }
+    }
+
+    progress_bar.finish_and_clear();
+    info!("{}", progress::format_summary(written, start.elapsed()));
+
+    if let Some(timings) = options.timings {
+        timings.add("download", start.elapsed());
+    }
+
+    match expected_size {
+        Some(expected_size) if expected_size != written => {
+            Err(Error::IncompleteDownload(expected_size, written))
+        }
+        _ => Ok(written),
+    }
+}
+
+/// The size is either the `Content-Length` or the number of bytes received.
+pub(crate) fn check_download_size(size: Option<u64>, max_size: Option<u64>) -> Result<(), Error> {
+    match (size, max_size) {
+        (Some(size), Some(max_size)) if size > max_size => Err(Error::DownloadTooLarge(max_size)),
+        _ => Ok(()),
+    }
+}
+
+/// Fails instead of writing more than `max_size` bytes, counting one more byte than the maximum
+/// so the download is known to be too large.
+struct SizeLimitedWriter<W: Write> {
+    inner: W,
+    written: u64,
+    max_size: Option<u64>,
+}
+
+impl<W: Write> Write for SizeLimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(max_size) = self.max_size {
+            if self.written + buf.len() as u64 > max_size {
+                self.written = max_size + 1;
+
+                return Err(std::io::Error::other("the download is too large"));
+            }
+        }
+
+        let written = self.inner.write(buf)?;
+
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Check and extract the archive downloaded from `url` into `file`.
+fn extract_downloaded_archive(
+    mut file: fs::File,
+    url: &str,
+    output_directory: &Path,
+    browser: Browser,
+    platform: Platform,
+    options: &DownloadOptions,
+) -> Result<(), Error> {
+    let executable_name = browser.get_driver_executable_name(platform);
+    let executable_names = browser.get_driver_executable_names(platform);
+    let executable_names = executable_names
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    // Not counted in the extraction, which is measured around it.
+    let verification_time = std::cell::Cell::new(Duration::ZERO);
+    let verify = |driver_directory: &Path| match options.verify_version {
+        Some(version) => {
+            let start = Instant::now();
+            let result = verify_driver(driver_directory, browser, platform, version);
+
+            verification_time.set(verification_time.get() + start.elapsed());
+            result
+        }
+        None => Ok(()),
+    };
+    let start = Instant::now();
+
+    file.seek(SeekFrom::Start(0))?;
+
+    let is_tar_gz = is_tar_gz(url, &mut file)?;
+
+    check_archive_content(&mut file, if is_tar_gz { &GZIP_MAGIC } else { &ZIP_MAGIC })?;
+
+    if let Some(expected_sha256) = options.expected_sha256 {
+        verify_checksum(&mut file, expected_sha256)?;
+        file.seek(SeekFrom::Start(0))?;
+    }
+
+    let result = if is_tar_gz {
+        extract_tar_gz(file, output_directory, &executable_names, &verify)
+    } else {
+        extract_archive(file, output_directory, &executable_names, &verify)
+    };
+
+    if let Some(timings) = options.timings {
+        let verification_time = verification_time.get();
+
+        timings.add(
+            "extraction",
+            start.elapsed().saturating_sub(verification_time),
+        );
+
+        if options.verify_version.is_some() {
+            timings.add("verification", verification_time);
+        }
+    }
+
+    result?;
+
+    match options.driver_name {
+        Some(driver_name) if driver_name != executable_name => replace_path(
+            &output_directory.join(executable_name),
+            &output_directory.join(driver_name),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Checked before downloading anything, so a wrong path fails early.
+fn create_output_directory(output_directory: &Path) -> Result<(), Error> {
+    let to_error = |error| Error::InvalidOutputDirectory(output_directory.to_path_buf(), error);
+
+    if output_directory.exists() && !output_directory.is_dir() {
+        return Err(to_error(std::io::Error::other("it is not a directory")));
+    }
+
+    std::fs::create_dir_all(output_directory).map_err(to_error)
+}
+
+/// Create the output directory if needed and check a file can be created in it, so a permission
+/// problem is reported before downloading anything.
+pub fn check_output_directory(output_directory: &Path) -> Result<(), Error> {
+    create_output_directory(output_directory)?;
+    tempfile::tempfile_in(output_directory)
+        .map(drop)
+        .map_err(|error| Error::OutputDirectoryNotWritable(output_directory.to_path_buf(), error))
+}
+
+/// Called with the directory of the extracted driver before it is installed.
+type VerifyFn<'a> = &'a dyn Fn(&Path) -> Result<(), Error>;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZIP_MAGIC: [u8; 4] = *b"PK\x03\x04";
+
+/// Look for the gzip magic number when the extension of the URL is not conclusive,
+/// the reader is rewound to its start.
+fn is_tar_gz<R: Read + Seek>(url: &str, reader: &mut R) -> Result<bool, Error> {
+    if url.ends_with(".tar.gz") || url.ends_with(".tgz") {
+        return Ok(true);
+    }
+
+    let mut magic = [0u8; 2];
+    let is_gzip = reader.read_exact(&mut magic).is_ok() && magic == GZIP_MAGIC;
+
+    reader.seek(SeekFrom::Start(0))?;
+
+    Ok(is_gzip)
+}
+
+/// Check the download starts with the magic number of its archive format, and otherwise
+/// report the beginning of what was received instead, like the HTML page of a proxy.
+/// The reader is rewound to its start.
+fn check_archive_content<R: Read + Seek>(reader: &mut R, magic: &[u8]) -> Result<(), Error> {
+    const SNIPPET_LENGTH: u64 = 100;
+
+    let mut start = Vec::new();
+
+    reader
+        .by_ref()
+        .take(SNIPPET_LENGTH)
+        .read_to_end(&mut start)?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    if start.starts_with(magic) {
+        return Ok(());
+    }
+
+    let snippet = String::from_utf8_lossy(&start)
+        .split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Err(Error::UnexpectedContent(if snippet.is_empty() {
+        String::from("an empty response")
+    } else {
+        format!("'{}'", snippet)
+    }))
+}
+
+fn verify_checksum<R: Read>(reader: &mut R, expected_sha256: &str) -> Result<(), Error> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+
+    std::io::copy(reader, &mut hasher)?;
+
+    let sha256 = format!("{:x}", hasher.finalize());
+
+    if !sha256.eq_ignore_ascii_case(expected_sha256) {
+        return Err(Error::ChecksumMismatch(expected_sha256.to_string(), sha256));
+    }
+
+    Ok(())
+}
+
+/// Extract a zip archive in the output directory.
+///
+/// Chrome for Testing archives have all their files in a single top-level directory
+/// named after the platform (e.g. `chromedriver-linux64/chromedriver`), this directory is
+/// flattened so the driver always ends up directly in the output directory.
+///
+/// The archive is extracted in a temporary directory first, so an interrupted extraction
+/// leaves the installed driver untouched. Each file gets the Unix permissions stored in its
+/// entry, and the entries whose path leaves the archive are skipped.
+fn extract_archive<R: Read + Seek>(
+    reader: R,
+    output_directory: &Path,
+    executable_names: &[&str],
+    verify: VerifyFn,
+) -> Result<(), Error> {
+    let mut archive = zip::read::ZipArchive::new(reader)?;
+    let extraction_directory = tempfile::tempdir_in(output_directory)?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let Some(path) = entry
+            .enclosed_name()
+            .map(|name| extraction_directory.path().join(name))
+        else {
+            debug!(
+                "Skipping '{}', its path is not in the archive",
+                entry.name()
+            );
+            continue;
+        };
+
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        std::io::copy(&mut entry, &mut fs::File::create(&path)?)?;
+
+        if let Some(mode) = entry.unix_mode() {
+            set_permissions(&path, mode)?;
+        }
+    }
+
+    install_extracted_files(
+        extraction_directory.path(),
+        output_directory,
+        executable_names,
+        verify,
+    )
+}
+
+/// Extract a gzipped tarball in the output directory, like [`extract_archive`].
+fn extract_tar_gz<R: Read>(
+    reader: R,
+    output_directory: &Path,
+    executable_names: &[&str],
+    verify: VerifyFn,
+) -> Result<(), Error> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(reader));
+    let extraction_directory = tempfile::tempdir_in(output_directory)?;
+
+    // Like the files extracted from a zip, the modification time is the one of the install.
+    archive.set_preserve_mtime(false);
+
+    archive
+        .unpack(extraction_directory.path())
+        .map_err(Error::TarExtractionFailed)?;
+
+    install_extracted_files(
+        extraction_directory.path(),
+        output_directory,
+        executable_names,
+        verify,
+    )
+}
+
+/// Make the extracted driver executable and verify it, then move the extracted files to the
+/// output directory. Each file is renamed so it is either the previous one or the new one.
+///
+/// The driver gets the first of `executable_names`, its standard name, when the archive has it
+/// under one of the other names, like `chromedriver-linux64`.
+fn install_extracted_files(
+    extraction_directory: &Path,
+    output_directory: &Path,
+    executable_names: &[&str],
+    verify: VerifyFn,
+) -> Result<(), Error> {
+    let archive_root = find_archive_root(extraction_directory)?;
+    let Some((executable_name, candidates)) = executable_names.split_first() else {
+        return verify(&archive_root);
+    };
+    let executable_path = archive_root.join(executable_name);
+
+    if !executable_path.exists() {
+        if let Some(candidate) = candidates
+            .iter()
+            .map(|name| archive_root.join(name))
+            .find(|path| path.is_file())
+        {
+            debug!(
+                "Renaming '{}' to '{}'",
+                candidate.display(),
+                executable_path.display()
+            );
+            fs::rename(candidate, &executable_path)?;
+        }
+    }
+
+    if executable_path.exists() {
+        make_executable(&executable_path)?;
+    }
+
+    verify(&archive_root)?;
+
+    for entry in fs::read_dir(&archive_root)? {
+        let entry = entry?;
+
+        replace_path(&entry.path(), &output_directory.join(entry.file_name()))?;
+    }
+
+    Ok(())
+}
+
+/// Rename `source` to `target`. When this fails, because `target` is a non-empty directory
+/// or an executable in use on Windows, `target` is first renamed out of the way, which Windows
+/// allows for running executables, then deleted if possible.
+fn replace_path(source: &Path, target: &Path) -> Result<(), Error> {
+    let error = match fs::rename(source, target) {
+        Ok(()) => return Ok(()),
+        Err(error) => error,
+    };
+
+    if !target.exists() {
+        return Err(error.into());
+    }
+
+    let mut previous_name = target.file_name().unwrap_or_default().to_os_string();
+
+    previous_name.push(".old");
+
+    let previous = target.with_file_name(previous_name);
+
+    remove_path(&previous).ok();
+    fs::rename(target, &previous)?;
+    fs::rename(source, target)?;
+
+    if let Err(error) = remove_path(&previous) {
+        debug!("Can't remove '{}': {}", previous.display(), error);
+    }
+
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Some archives do not store the permissions of the driver, it must be made executable
+/// or running it fails with "permission denied". The permissions it was stored with are kept
+/// when it is executable.
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if fs::metadata(path)?.permissions().mode() & 0o100 == 0 {
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Only the permission bits are applied, not the file type or the setuid, setgid and sticky
+/// bits.
+#[cfg(unix)]
+fn set_permissions(path: &Path, mode: u32) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_permissions(_path: &Path, _mode: u32) -> Result<(), Error> {
+    Ok(())
+}
+
+fn find_archive_root(extraction_directory: &Path) -> Result<PathBuf, Error> {
+    let entries = fs::read_dir(extraction_directory)?.collect::<Result<Vec<_>, _>>()?;
+
+    match entries.as_slice() {
+        [entry] if entry.file_type()?.is_dir() => Ok(entry.path()),
+        _ => Ok(extraction_directory.to_path_buf()),
+    }
+}
+
+/// `base_url` replaces [`DEFAULT_BASE_URL`] with a mirror, which also serves the drivers of
+/// Chrome for Testing with the layout of storage.googleapis.com/chrome-for-testing-public.
+pub fn get_download_url(
+    required_version: Version,
+    browser: Browser,
+    platform: Platform,
+    base_url: &str,
+) -> String {
+    match browser {
+        Browser::Chrome => {}
+        Browser::Edge => return edge::get_download_url(required_version, platform),
+        Browser::Firefox => return geckodriver::get_download_url(required_version, platform),
+    }
+
+    format_chromedriver_url(
+        required_version,
+        match required_version.major >= chrome_for_testing::FIRST_MILESTONE {
+            true => platform.get_chrome_for_testing_key(),
+            false => platform.get_key(),
+        },
+        base_url,
+    )
+}
+
+/// Like [`get_download_url`] for chromedriver, with a platform key used verbatim instead of the
+/// ones of [`Platform`], for a platform published before it is supported, like `linux-arm64`.
+pub fn get_chromedriver_download_url(
+    required_version: Version,
+    platform_key: &str,
+    base_url: &str,
+) -> String {
+    format_chromedriver_url(required_version, platform_key, base_url)
+}
+
+/// The URL to download the driver from, the one listed by Chrome for Testing for its versions
+/// of chromedriver, unless a mirror replaces [`DEFAULT_BASE_URL`]. Otherwise the URL of
+/// [`get_download_url`].
+pub fn resolve_download_url(
+    client: &HttpClient,
+    required_version: Version,
+    browser: Browser,
+    platform: Platform,
+    base_url: &str,
+) -> Result<String, Error> {
+    match browser == Browser::Chrome && is_listed(required_version, base_url) {
+        true => chrome_for_testing::get_listed_download_url(
+            client,
+            required_version,
+            platform.get_chrome_for_testing_key(),
+        ),
+        false => Ok(get_download_url(
+            required_version,
+            browser,
+            platform,
+            base_url,
+        )),
+    }
+}
+
+/// Like [`resolve_download_url`] for chromedriver, with a platform key used verbatim, see
+/// [`get_chromedriver_download_url`].
+pub fn resolve_chromedriver_download_url(
+    client: &HttpClient,
+    required_version: Version,
+    platform_key: &str,
+    base_url: &str,
+) -> Result<String, Error> {
+    match is_listed(required_version, base_url) {
+        true => chrome_for_testing::get_listed_download_url(client, required_version, platform_key),
+        false => Ok(get_chromedriver_download_url(
+            required_version,
+            platform_key,
+            base_url,
+        )),
+    }
+}
+
+/// Whether the download URL of a chromedriver is the one listed by Chrome for Testing, a mirror
+/// is only expected to have its layout.
+fn is_listed(required_version: Version, base_url: &str) -> bool {
+    required_version.major >= chrome_for_testing::FIRST_MILESTONE
+        && !chrome_for_testing::is_mirror(base_url)
+}
+
+/// `platform_key` is a key of Chrome for Testing or a legacy one, depending on the version.
+fn format_chromedriver_url(
+    required_version: Version,
+    platform_key: &str,
+    base_url: &str,
+) -> String {
+    if required_version.major >= chrome_for_testing::FIRST_MILESTONE {
+        return format!(
+            "{}/{}/{}/chromedriver-{}.zip",
+            chrome_for_testing::get_download_base_url(base_url),
+            required_version,
+            platform_key,
+            platform_key
+        );
+    }
+
+    format!(
+        "{}/{}.{}.{}.{}/chromedriver_{}.zip",
+        base_url.trim_end_matches('/'),
+        required_version.major,
+        required_version.minor,
+        required_version.build,
+        required_version.patch,
+        platform_key
+    )
+}
+
+fn run_program<I, S>(program_path: &Path, arguments: I) -> Result<String, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    run_program_with_stderr(program_path, arguments).map(|(stdout, _)| stdout)
+}
+
+/// Run a program and return its stdout and stderr.
+fn run_program_with_stderr<I, S>(
+    program_path: &Path,
+    arguments: I,
+) -> Result<(String, String), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    use std::process::Command;
+
+    if !program_path.exists() {
+        return Err(Error::ProgramDoesNotExist(program_path.to_path_buf()));
+    }
+
+    let arguments = arguments.into_iter().collect::<Vec<_>>();
+    let (stdout, stderr) =
+        process::get_output(program_path, Command::new(program_path).args(&arguments))?;
+    let stdout = String::from_utf8_lossy(stdout.as_slice()).to_string();
+    let stderr = String::from_utf8_lossy(stderr.as_slice()).to_string();
+
+    debug!(
+        "Output of '{}' {:?}: {:?}, stderr: {:?}",
+        program_path.display(),
+        arguments
+            .iter()
+            .map(|argument| argument.as_ref())
+            .collect::<Vec<_>>(),
+        stdout,
+        stderr
+    );
+
+    Ok((stdout, stderr))
+}
+
+/// On macOS the version is read from the bundle when `program_path` is an application bundle
+/// or its executable, running the browser is slow and can launch it.
+#[cfg(not(target_os = "windows"))]
+pub fn get_local_browser_version(program_path: &Path, browser: Browser) -> Result<Version, Error> {
+    check_browser_exists(program_path, browser)?;
+
+    #[cfg(target_os = "macos")]
+    if let Some(bundle) = app_bundle::get_bundle_directory(program_path) {
+        match app_bundle::get_bundle_version(&bundle) {
+            Ok(version) => {
+                channel::warn_if_not_stable(browser, program_path, "");
+
+                return Ok(version);
+            }
+            Err(error) => debug!("Can't read the version of the bundle: {}", error),
+        }
+    }
+
+    let program_path = &get_browser_executable(program_path)?;
+
+    // Falls back to running the launcher, like `flatpak run <app id> --version`.
+    #[cfg(target_os = "linux")]
+    if let Some(app_id) = flatpak::get_app_id(program_path) {
+        match flatpak::get_version(app_id) {
+            Ok(version) => return Ok(version),
+            Err(error) => debug!("Can't read the version of the Flatpak: {}", error),
+        }
+    }
+
+    let (stdout, stderr) = run_program_with_stderr(program_path, ["--version"])?;
+    let parser = match browser {
+        Browser::Chrome => parsers::parse_chromium_version_output,
+        Browser::Edge => parsers::parse_edge_version_output,
+        Browser::Firefox => parsers::parse_firefox_version_output,
+    };
+    let version = parse_any_output_line(program_path, &stdout, &stderr, parser)?;
+
+    channel::warn_if_not_stable(browser, program_path, &stdout);
+
+    Ok(version)
+}
+
+/// Read the version of the browser from the first line of a file, for the builds where the
+/// browser can't be run. The patch number can be omitted, like `120.0.6099`.
+pub fn read_browser_version_file(path: &Path) -> Result<Version, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|error| Error::CantReadVersionFile(path.to_path_buf(), error))?;
+    let line = content.lines().next().unwrap_or_default().trim();
+
+    Version::from_partial_str(line).map_err(|_| {
+        Error::ParsingVersionFailed(format!(
+            "the first line of '{}', '{}', is not a valid version",
+            path.display(),
+            line
+        ))
+    })
+}
+
+/// A wrong location is the most common mistake, reported apart from the other failures to run
+/// the browser.
+fn check_browser_exists(program_path: &Path, browser: Browser) -> Result<(), Error> {
+    if program_path.exists() {
+        Ok(())
+    } else {
+        Err(Error::BrowserNotInstalled(
+            browser,
+            program_path.to_path_buf(),
+        ))
+    }
+}
+
+/// The executable to run for `program_path`, which may be a directory passed by mistake.
+/// On macOS, the executable of an application bundle is found in its `Contents/MacOS`.
+fn get_browser_executable(program_path: &Path) -> Result<PathBuf, Error> {
+    if !program_path.is_dir() {
+        return Ok(program_path.to_path_buf());
+    }
+
+    #[cfg(target_os = "macos")]
+    if let Some(executable) = app_bundle::get_bundle_executable(program_path) {
+        debug!("Running '{}' of the bundle", executable.display());
+
+        return Ok(executable);
+    }
+
+    Err(Error::NotAnExecutable(program_path.to_path_buf()))
+}
+
+/// The browsers packaged as snaps can print warnings before their version, and some builds print
+/// it on stderr, so each line of both is tried. The error is the one of the whole stdout.
+#[cfg(not(target_os = "windows"))]
+fn parse_any_output_line(
+    program_path: &Path,
+    stdout: &str,
+    stderr: &str,
+    parser: fn(&str) -> nom::IResult<&str, Version>,
+) -> Result<Version, Error> {
+    if stdout.trim().is_empty() && stderr.trim().is_empty() {
+        return Err(Error::EmptyVersionOutput(program_path.to_path_buf()));
+    }
+
+    let error = match parser(stdout) {
+        Ok((_, version)) => return Ok(version),
+        Err(error) => error,
+    };
+
+    stdout
+        .lines()
+        .chain(stderr.lines())
+        .find_map(|line| parser(line).ok())
+        .map(|(_, version)| version)
+        .ok_or_else(|| Error::ParsingVersionFailed(error.to_string()))
+}
+
+/// On Windows, Google Chrome and Microsoft Edge ignore the flag --version or --product-version
+/// See https://bugs.chromium.org/p/chromium/issues/detail?id=158372
+///
+/// The registry is read first as it does not require to spawn any process, then PowerShell
+/// is tried before WMIC because WMIC is deprecated and missing on recent Windows 11 builds.
+/// Firefox does not have this issue.
+#[cfg(target_os = "windows")]
+pub fn get_local_browser_version(program_path: &Path, browser: Browser) -> Result<Version, Error> {
+    check_browser_exists(program_path, browser)?;
+
+    let program_path = &get_browser_executable(program_path)?;
+    let blbeacon_key = match browser {
+        Browser::Chrome => "Software\\Google\\Chrome\\BLBeacon",
+        Browser::Edge => "Software\\Microsoft\\Edge\\BLBeacon",
+        Browser::Firefox => {
+            let stdout = run_program(program_path, ["--version"])?;
+
+            return parsers::parse_firefox_version_output(&stdout)
+                .map_err(|error| Error::ParsingVersionFailed(error.to_string()))
+                .map(|(_, version)| version);
+        }
+    };
+
+    let version = get_local_browser_version_from_registry(blbeacon_key)
+        .or_else(|_| get_local_browser_version_with_powershell(program_path))
+        .or_else(|_| get_local_browser_version_with_wmic(program_path))?;
+
+    channel::warn_if_not_stable(browser, program_path, "");
+
+    Ok(version)
+}
+
+/// Chrome writes its version in the key `Software\Google\Chrome\BLBeacon`, and Edge in
+/// `Software\Microsoft\Edge\BLBeacon`, either for the current user or for the whole machine
+/// depending on how it was installed.
+#[cfg(target_os = "windows")]
+fn get_local_browser_version_from_registry(blbeacon_key: &str) -> Result<Version, Error> {
+    use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+    use winreg::RegKey;
+
+    let key = RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(blbeacon_key)
+        .or_else(|_| RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(blbeacon_key))?;
+    let version: String = key.get_value("version")?;
+
+    parsers::parse_partial_version_numbers(&version)
+        .map_err(|error| Error::ParsingVersionFailed(error.to_string()))
+        .map(|(_, version)| version)
+}
+
+#[cfg(target_os = "windows")]
+fn get_local_browser_version_with_powershell(program_path: &Path) -> Result<Version, Error> {
+    // In a single-quoted PowerShell string the only character to escape is the single quote.
+    let escaped_path = program_path.display().to_string().replace('\'', "''");
+    let stdout = run_program(
+        Path::new("C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe"),
+        [
+            "-NoProfile",
+            "-Command",
+            &format!("(Get-Item '{}').VersionInfo.ProductVersion", escaped_path),
+        ],
+    )?;
+
+    parsers::parse_powershell_version(&stdout)
+        .map_err(|error| Error::ParsingVersionFailed(error.to_string()))
+        .map(|(_, version)| version)
+}
+
+#[cfg(target_os = "windows")]
+fn get_local_browser_version_with_wmic(program_path: &Path) -> Result<Version, Error> {
+    let stdout = run_program(
+        Path::new("C:\\Windows\\System32\\wbem\\WMIC.exe"),
+        [
+            "datafile",
+            "where",
+            &format_wmic_name_filter(&program_path.display().to_string()),
+            "get",
+            "Version",
+            "/value",
+        ],
+    )?;
+
+    parsers::parse_wmic_version(&stdout)
+        .map_err(|error| Error::ParsingVersionFailed(error.to_string()))
+        .map(|(_, version)| version)
+}
+
+/// The WQL filter matching the file at `program_path`, like
+/// `name='C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe'`. In a WQL string the
+/// backslashes and the quotes are escaped with a backslash, and WMIC does not understand the
+/// `\\?\` prefix of the long paths. The filter is single-quoted so it is passed as one argument
+/// without double quotes inside it, which WMIC splits on, even with spaces or parentheses.
+#[cfg(any(target_os = "windows", test))]
+fn format_wmic_name_filter(program_path: &str) -> String {
+    let program_path = match program_path.strip_prefix("\\\\?\\") {
+        Some(path) => match path.strip_prefix("UNC\\") {
+            Some(share) => format!("\\\\{}", share),
+            None => path.to_string(),
+        },
+        None => program_path.to_string(),
+    };
+    let escaped_path = program_path.replace('\\', "\\\\").replace('\'', "\\'");
+
+    format!("name='{}'", escaped_path)
+}
+
+// On Windows Chrome.exe seems to ignore all the arguments passed to the command line.
+// Found this hackish way on stackoverflow..
+// https://stackoverflow.com/questions/50880917/how-to-get-chrome-version-using-command-prompt-in-windows
+
+/// Since Chrome 115 the drivers are no longer published on chromedriver.storage.googleapis.com.
+/// See https://chromedriver.chromium.org/downloads/version-selection
+///
+/// A Microsoft Edge WebDriver is published for each version of Edge, so no lookup is required.
+/// geckodriver releases are not tied to a version of Firefox, the newest one supporting it is used.
+///
+/// `base_url` replaces [`DEFAULT_BASE_URL`] with a mirror, which also serves the JSON endpoints
+/// of Chrome for Testing at its root.
+pub fn get_required_driver_version(
+    client: &HttpClient,
+    browser: Browser,
+    chrome_version: Version,
+    base_url: &str,
+) -> Result<Version, Error> {
+    match browser {
+        Browser::Chrome => {}
+        Browser::Edge => return Ok(chrome_version),
+        Browser::Firefox => {
+            return geckodriver::get_required_driver_version(client, chrome_version);
+        }
+    }
+
+    if chrome_version.major >= chrome_for_testing::FIRST_MILESTONE {
+        return chrome_for_testing::get_required_driver_version(client, chrome_version, base_url);
+    }
+
+    get_legacy_driver_version(client, base_url.trim_end_matches('/'), chrome_version)
+}
+
+/// Query the latest release for `major.minor.build`, and if nothing is published for it
+/// fall back to `major.minor` then to `major`, and at last to the listing of the storage.
+fn get_legacy_driver_version(
+    client: &HttpClient,
+    base_url: &str,
+    chrome_version: Version,
+) -> Result<Version, Error> {
+    for candidate in get_legacy_candidates(chrome_version) {
+        let url = format!("{}/LATEST_RELEASE_{}", base_url, candidate);
+        let response = match client.get_text(&url) {
+            Ok(response) => response,
+            Err(error) if error.is_not_found() => {
+                debug!("No release published for {}", candidate);
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+
+        return parse_legacy_response(&candidate, &response);
+    }
+
+    debug!("No latest release published, looking in the listing of the storage");
+
+    match storage_listing::get_driver_version(client, base_url, chrome_version) {
+        Err(error) if error.is_not_found() => Err(Error::NoDriverForVersion(chrome_version)),
+        result => result,
+    }
+}
+
+/// The prefixes of `LATEST_RELEASE_<prefix>` tried in order.
+fn get_legacy_candidates(chrome_version: Version) -> [String; 3] {
+    [
+        format!(
+            "{}.{}.{}",
+            chrome_version.major, chrome_version.minor, chrome_version.build
+        ),
+        format!("{}.{}", chrome_version.major, chrome_version.minor),
+        format!("{}", chrome_version.major),
+    ]
+}
+
+fn parse_legacy_response(candidate: &str, response: &str) -> Result<Version, Error> {
+    debug!("Latest release for {}: {:?}", candidate, response);
+
+    parsers::parse_version_numbers(response)
+        .map_err(|error| Error::ParsingVersionFailed(error.to_string()))
+        .map(|(_, version)| version)
+}
+
+/// The version of the driver in `driver_directory`, probing each name of
+/// [`Browser::get_driver_executable_names`] and using the first reporting a valid version.
+/// When none does, the error of the first driver found, or `None` if there is none.
+pub fn get_local_driver_version(
+    driver_directory: &Path,
+    browser: Browser,
+    platform: Platform,
+) -> Result<Option<Version>, Error> {
+    get_local_driver_version_and_revision(driver_directory, browser, platform)
+        .map(|driver| driver.map(|(version, _)| version))
+}
+
+/// Like [`get_local_driver_version`] with the revision of the driver, the hash of the commit
+/// it was built from, to know where an installed driver comes from. `None` for a driver not
+/// printing it.
+pub fn get_local_driver_version_and_revision(
+    driver_directory: &Path,
+    browser: Browser,
+    platform: Platform,
+) -> Result<Option<(Version, Option<String>)>, Error> {
+    let mut first_error = None;
+
+    for name in browser.get_driver_executable_names(platform) {
+        match get_local_driver_version_and_revision_at(&driver_directory.join(&name), browser) {
+            Ok(Some(driver)) => return Ok(Some(driver)),
+            Ok(None) => {}
+            Err(error) => {
+                debug!("Can't get the version of '{}': {}", name, error);
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+
+    first_error.map_or(Ok(None), Err)
+}
+
+/// Like [`get_local_driver_version`] for a driver installed with another name than its
+/// standard one. `None` if there is no driver at `program_path`.
+pub fn get_local_driver_version_at(
+    program_path: &Path,
+    browser: Browser,
+) -> Result<Option<Version>, Error> {
+    get_local_driver_version_and_revision_at(program_path, browser)
+        .map(|driver| driver.map(|(version, _)| version))
+}
+
+/// Like [`get_local_driver_version_and_revision`] for a driver installed with another name than
+/// its standard one.
+pub fn get_local_driver_version_and_revision_at(
+    program_path: &Path,
+    browser: Browser,
+) -> Result<Option<(Version, Option<String>)>, Error> {
+    if !program_path.exists() {
+        return Ok(None);
+    }
+
+    let stdout = run_program(program_path, ["--version"])?;
+    let result = match browser {
+        Browser::Chrome => parsers::parse_chromedriver_version_output(&stdout),
+        Browser::Edge => parsers::parse_edgedriver_version_output(&stdout),
+        Browser::Firefox => parsers::parse_geckodriver_version_output(&stdout),
+    };
+    let (remaining, version) =
+        result.map_err(|error| Error::ParsingVersionFailed(error.to_string()))?;
+
+    let revision = parsers::parse_driver_revision(remaining)
+        .ok()
+        .map(|(_, revision)| revision.to_string());
+
+    if let Some(revision) = &revision {
+        debug!("Revision of '{}': {}", program_path.display(), revision);
+    }
+
+    Ok(Some((version, revision)))
+}
+
+/// Run the installed driver to check it is working and is the required version, a driver
+/// for another architecture or a corrupted one fails to run.
+pub fn verify_driver(
+    driver_directory: &Path,
+    browser: Browser,
+    platform: Platform,
+    required_version: Version,
+) -> Result<(), Error> {
+    match get_local_driver_version(driver_directory, browser, platform) {
+        Ok(Some(version)) if version == required_version => Ok(()),
+        Ok(Some(version)) => Err(Error::DriverVerificationFailed(format!(
+            "expected version {}, got {}",
+            required_version, version
+        ))),
+        Ok(None) => Err(Error::DriverVerificationFailed(format!(
+            "'{}' not found",
+            browser.get_driver_executable_name(platform)
+        ))),
+        Err(error) => Err(Error::DriverVerificationFailed(error.to_string())),
+    }
+}
+
+/// Start the driver on a free port and wait until it reports it is listening, then stop it.
+/// Catches the failures happening after `--version`, like a library that can't be loaded.
+pub fn smoke_test_driver(driver_path: &Path, browser: Browser) -> Result<(), Error> {
+    use std::process::Command;
+
+    // The port may be taken again before the driver binds it, which is unlikely on the loopback.
+    let port = std::net::TcpListener::bind(("127.0.0.1", 0))?
+        .local_addr()?
+        .port();
+    let expected = match browser {
+        // `ChromeDriver was started successfully.` or `msedgedriver was started successfully.`
+        Browser::Chrome | Browser::Edge => "was started successfully",
+        Browser::Firefox => "Listening on",
+    };
+
+    debug!(
+        "Starting '{}' on port {} for a smoke test",
+        driver_path.display(),
+        port
+    );
+
+    process::wait_for_line(
+        driver_path,
+        Command::new(driver_path).arg(format!("--port={}", port)),
+        expected,
+        process::get_program_timeout(),
+    )
+}
+
+/// Options of [`ensure_driver`] and [`check_update`].
+#[derive(Debug, Clone)]
+pub struct EnsureOptions {
+    /// The browser whose driver is installed.
+    pub browser: Browser,
+
+    /// The version of the browser, used instead of running it.
+    pub browser_version: Option<Version>,
+
+    /// Options of the client used for the lookup and the download.
+    pub http: HttpOptions,
+
+    /// Replaces [`DEFAULT_BASE_URL`], see [`get_required_driver_version`].
+    pub base_url: String,
+
+    /// Draw the progress of the download on stderr, disabled by default so the output of a
+    /// build script stays clean.
+    pub show_progress: bool,
+
+    /// How long to wait for the other updates of `output_directory`, see
+    /// [`lock_output_directory`].
+    pub lock_timeout: Duration,
+}
+
+impl Default for EnsureOptions {
+    fn default() -> Self {
+        Self {
+            browser: Browser::default(),
+            browser_version: None,
+            http: HttpOptions::default(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            show_progress: false,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+        }
+    }
+}
+
+/// Install the driver matching the browser in `output_directory` if it is missing or outdated,
+/// and return the location of its executable. Nothing is downloaded when it is up to date.
+/// The browser is searched with [`find_browser`] if `browser_path` is `None`.
+///
+/// Only the [`log`] crate is used to report what is done, nothing is printed.
+pub fn ensure_driver(
+    browser_path: Option<&Path>,
+    output_directory: &Path,
+    options: &EnsureOptions,
+) -> Result<PathBuf, Error> {
+    let browser = options.browser;
+    let platform = Platform::default();
+    let browser_version = get_browser_version(browser_path, options)?;
+    let client = HttpClient::new(&options.http)?;
+    let required_version =
+        get_required_driver_version(&client, browser, browser_version, &options.base_url)?;
+
+    create_output_directory(output_directory)?;
+
+    // Another build may install the same driver, it is then up to date once the lock is taken.
+    let _lock = lock_output_directory(output_directory, options.lock_timeout)?;
+    // A broken driver is replaced.
+    let current_version = get_local_driver_version(output_directory, browser, platform)
+        .unwrap_or_else(|error| {
+            debug!("Can't get the version of the current driver: {}", error);
+            None
+        });
+
+    if must_update(current_version, required_version, MatchPolicy::Exact) {
+        let url = resolve_download_url(
+            &client,
+            required_version,
+            browser,
+            platform,
+            &options.base_url,
+        )?;
+
+        debug!("Download {}", url);
+        download_and_extract(
+            &client,
+            &url,
+            output_directory,
+            browser,
+            platform,
+            &DownloadOptions {
+                expected_sha256: None,
+                show_progress: options.show_progress,
+                verify_version: Some(required_version),
+                driver_name: None,
+                max_download_size: None,
+                progress_label: None,
+                timings: None,
+            },
+        )
+        .map_err(|error| match error {
+            error if error.is_not_found() => Error::NoDriverForVersion(required_version),
+            error => error,
+        })?;
+    }
+
+    Ok(output_directory.join(browser.get_driver_executable_name(platform)))
+}
+
+/// The version of [`EnsureOptions::browser_version`], or the one of the browser at
+/// `browser_path` or found with [`find_browser`].
+fn get_browser_version(
+    browser_path: Option<&Path>,
+    options: &EnsureOptions,
+) -> Result<Version, Error> {
+    match (options.browser_version, browser_path) {
+        (Some(version), _) => Ok(version),
+        (None, Some(path)) => get_local_browser_version(path, options.browser),
+        (None, None) => get_local_browser_version(
+            &find_browser(options.browser, Channel::Stable)?,
+            options.browser,
+        ),
+    }
+}
+
+/// Whether the driver installed in the output directory must be updated, see [`check_update`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateStatus {
+    /// The installed driver is the required version or newer.
+    UpToDate { version: Version },
+
+    /// The installed driver is older, `from` is `None` when it can't be run, like a broken
+    /// driver or the driver of another platform.
+    UpdateAvailable { from: Option<Version>, to: Version },
+
+    /// No driver is installed.
+    NotInstalled { to: Version },
+}
+
+/// Same as [`ensure_driver`] without installing anything, like the `check` command: find the
+/// driver required by the browser and compare it to the one installed in `output_directory`
+/// for `platform`. Nothing is printed or written.
+pub fn check_update(
+    browser_path: Option<&Path>,
+    output_directory: &Path,
+    platform: Platform,
+    options: &EnsureOptions,
+) -> Result<UpdateStatus, Error> {
+    let browser_version = get_browser_version(browser_path, options)?;
+    let client = HttpClient::new(&options.http)?;
+    let required_version =
+        get_required_driver_version(&client, options.browser, browser_version, &options.base_url)?;
+    let is_installed = options
+        .browser
+        .get_driver_executable_names(platform)
+        .iter()
+        .any(|name| output_directory.join(name).exists());
+    let current_version =
+        match get_local_driver_version(output_directory, options.browser, platform) {
+            Ok(version) => version,
+            Err(error) => {
+                debug!("Can't get the version of the current driver: {}", error);
+                None
+            }
+        };
+
+    Ok(get_update_status(
+        is_installed,
+        current_version,
+        required_version,
+    ))
+}
+
+fn get_update_status(
+    is_installed: bool,
+    current_version: Option<Version>,
+    required_version: Version,
+) -> UpdateStatus {
+    match current_version {
+        _ if !is_installed => UpdateStatus::NotInstalled {
+            to: required_version,
+        },
+        Some(version) if !must_update(Some(version), required_version, MatchPolicy::Exact) => {
+            UpdateStatus::UpToDate { version }
+        }
+        from => UpdateStatus::UpdateAvailable {
+            from,
+            to: required_version,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        check_archive_content, check_download_size, check_output_directory, download_and_extract,
+        download_archive, extract_archive, extract_tar_gz, get_download_url, get_installed_names,
+        get_legacy_driver_version, get_update_status, is_driver_compatible, is_tar_gz, must_update,
+        preview_extraction, replace_path, test_server, verify_checksum, verify_driver, Browser,
+        DownloadOptions, Error, HttpClient, HttpOptions, MatchPolicy, PlannedFile, Platform,
+        SizeLimitedWriter, UpdateStatus, Version, DEFAULT_BASE_URL, GZIP_MAGIC, ZIP_MAGIC,
+    };
+    use std::io::{Cursor, Write};
+    use std::path::PathBuf;
+    use test_case::test_case;
+
+    fn make_zip(files: &[&str]) -> Cursor<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+        for file in files {
+            let options = zip::write::FileOptions::default().unix_permissions(0o644);
+
+            writer.start_file(*file, options).unwrap();
+            writer.write_all(file.as_bytes()).unwrap();
+        }
+
+        let mut archive = writer.finish().unwrap();
+
+        archive.set_position(0);
+        archive
+    }
+
+    fn make_tar_gz(files: &[&str]) -> Cursor<Vec<u8>> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+
+        for file in files {
+            let mut header = tar::Header::new_gnu();
+
+            header.set_size(file.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, file, file.as_bytes())
+                .unwrap();
+        }
+
+        Cursor::new(builder.into_inner().unwrap().finish().unwrap())
+    }
+
+    #[test_case(None, Version::new(114, 0, 5735, 90), true ; "not installed")]
+    #[test_case(Some(Version::new(114, 0, 5735, 90)), Version::new(114, 0, 5735, 90), false ; "same version")]
+    #[test_case(Some(Version::new(114, 0, 5735, 16)), Version::new(114, 0, 5735, 90), true ; "older patch")]
+    #[test_case(Some(Version::new(113, 0, 5672, 63)), Version::new(114, 0, 5735, 90), true ; "older major")]
+    #[test_case(Some(Version::new(114, 0, 5735, 90)), Version::new(113, 0, 5672, 63), false ; "newer local")]
+    #[test_case(Some(Version::new(115, 0, 5000, 2)), Version::new(114, 0, 6000, 1), false ; "newer major with lower build")]
+    fn test_must_update(current_version: Option<Version>, new_version: Version, expected: bool) {
+        assert_eq!(
+            expected,
+            must_update(current_version, new_version, MatchPolicy::Exact)
+        );
+    }
+
+    #[test_case(None, Version::new(114, 0, 5735, 90), true ; "not installed")]
+    #[test_case(Some(Version::new(114, 0, 5735, 90)), Version::new(114, 0, 5735, 90), false ; "same version")]
+    #[test_case(Some(Version::new(114, 0, 5735, 16)), Version::new(114, 0, 5735, 90), false ; "older patch")]
+    #[test_case(Some(Version::new(114, 0, 5700, 1)), Version::new(114, 0, 5735, 90), false ; "older build")]
+    #[test_case(Some(Version::new(113, 0, 5672, 63)), Version::new(114, 0, 5735, 90), true ; "older major")]
+    #[test_case(Some(Version::new(115, 0, 5790, 170)), Version::new(114, 0, 5735, 90), true ; "newer major")]
+    fn test_must_update_major(
+        current_version: Option<Version>,
+        new_version: Version,
+        expected: bool,
+    ) {
+        assert_eq!(
+            expected,
+            must_update(current_version, new_version, MatchPolicy::Major)
+        );
+    }
+
+    #[test_case("exact", Some(MatchPolicy::Exact) ; "exact")]
+    #[test_case("major", Some(MatchPolicy::Major) ; "major")]
+    #[test_case("minor", None ; "unsupported")]
+    fn test_parse_match_policy(input: &str, expected: Option<MatchPolicy>) {
+        assert_eq!(expected, input.parse().ok());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test_case("Chromium 120.0.6099.109 snap\n", "", Some(Version::new(120, 0, 6099, 109)) ; "snap")]
+    #[test_case("", "Chromium 120.0.6099.109 snap\n", Some(Version::new(120, 0, 6099, 109)) ; "snap on stderr")]
+    #[test_case("/user.slice/user-1000.slice/session-2.scope is not a snap cgroup\nChromium 120.0.6099.109 snap\n", "", Some(Version::new(120, 0, 6099, 109)) ; "snap cgroup warning")]
+    #[test_case("", "Gtk-Message: 10:42:01.123: Failed to load module \"canberra-gtk-module\"\nChromium 120.0.6099.109 snap\n", Some(Version::new(120, 0, 6099, 109)) ; "gtk warning on stderr")]
+    #[test_case("", "", None ; "empty")]
+    #[test_case("Chromium\n", "error\n", None ; "no version")]
+    fn test_parse_any_output_line(stdout: &str, stderr: &str, expected: Option<Version>) {
+        let result = crate::parse_any_output_line(
+            std::path::Path::new("chromium"),
+            stdout,
+            stderr,
+            crate::parsers::parse_chromium_version_output,
+        );
+
+        assert_eq!(expected, result.ok());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test_case("", "" ; "empty")]
+    #[test_case("\n", "  \n" ; "blank lines")]
+    fn test_parse_any_output_line_empty(stdout: &str, stderr: &str) {
+        let result = crate::parse_any_output_line(
+            std::path::Path::new("chromium"),
+            stdout,
+            stderr,
+            crate::parsers::parse_chromium_version_output,
+        );
+
+        assert!(
+            matches!(result, Err(Error::EmptyVersionOutput(path)) if path == std::path::Path::new("chromium"))
+        );
+    }
+
+    #[test_case(Browser::Chrome, Version::new(120, 0, 6099, 71), Version::new(120, 0, 6099, 109), true ; "chrome same major")]
+    #[test_case(Browser::Chrome, Version::new(121, 0, 6167, 85), Version::new(120, 0, 6099, 109), false ; "chrome other major")]
+    #[test_case(Browser::Edge, Version::new(120, 0, 2210, 91), Version::new(120, 0, 2210, 77), true ; "edge same major")]
+    #[test_case(Browser::Firefox, Version::new(128, 0, 0, 0), Version::new(0, 36, 0, 0), true ; "firefox supported")]
+    #[test_case(Browser::Firefox, Version::new(115, 0, 0, 0), Version::new(0, 36, 0, 0), false ; "firefox too old")]
+    #[test_case(Browser::Firefox, Version::new(128, 0, 0, 0), Version::new(0, 10, 0, 0), false ; "unknown geckodriver")]
+    fn test_is_driver_compatible(
+        browser: Browser,
+        browser_version: Version,
+        driver_version: Version,
+        expected: bool,
+    ) {
+        assert_eq!(
+            expected,
+            is_driver_compatible(browser, browser_version, driver_version)
+        );
+    }
+
+    #[test_case(&["chromedriver"] ; "legacy")]
+    #[test_case(&["chromedriver-linux64/chromedriver", "chromedriver-linux64/LICENSE.chromedriver"] ; "chrome for testing")]
+    fn test_extract_archive(files: &[&str]) {
+        let output_directory = tempfile::tempdir().unwrap();
+
+        extract_archive(
+            make_zip(files),
+            output_directory.path(),
+            &["chromedriver"],
+            &|_| Ok(()),
+        )
+        .unwrap();
+
+        let mut entries = std::fs::read_dir(output_directory.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+
+        entries.sort();
+
+        let mut expected = files
+            .iter()
+            .map(|file| std::path::Path::new(file).file_name().unwrap().to_owned())
+            .collect::<Vec<_>>();
+
+        expected.sort();
+
+        assert_eq!(expected, entries);
+    }
+
+    #[test]
+    fn test_extract_archive_suffixed_driver() {
+        let output_directory = tempfile::tempdir().unwrap();
+
+        extract_archive(
+            make_zip(&[
+                "chromedriver-linux64/chromedriver-linux64",
+                "chromedriver-linux64/LICENSE",
+            ]),
+            output_directory.path(),
+            &["chromedriver", "chromedriver-linux64"],
+            &|driver_directory| {
+                assert!(driver_directory.join("chromedriver").is_file());
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                (
+                    String::from("LICENSE"),
+                    String::from("chromedriver-linux64/LICENSE")
+                ),
+                (
+                    String::from("chromedriver"),
+                    String::from("chromedriver-linux64/chromedriver-linux64")
+                ),
+            ],
+            read_output_directory(output_directory.path())
+        );
+    }
+
+    #[test]
+    fn test_extract_tar_gz() {
+        let output_directory = tempfile::tempdir().unwrap();
+
+        extract_tar_gz(
+            make_tar_gz(&["geckodriver"]),
+            output_directory.path(),
+            &["geckodriver"],
+            &|_| Ok(()),
+        )
+        .unwrap();
+
+        let content = std::fs::read(output_directory.path().join("geckodriver")).unwrap();
+
+        assert_eq!(b"geckodriver".to_vec(), content);
+    }
+
+    #[test]
+    fn test_extract_tar_gz_invalid() {
+        let output_directory = tempfile::tempdir().unwrap();
+        let result = extract_tar_gz(
+            Cursor::new(b"not a tarball".to_vec()),
+            output_directory.path(),
+            &["geckodriver"],
+            &|_| Ok(()),
+        );
+
+        assert!(matches!(result, Err(Error::TarExtractionFailed(_))));
+    }
+
+    fn read_output_directory(output_directory: &std::path::Path) -> Vec<(String, String)> {
+        let mut entries = std::fs::read_dir(output_directory)
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+
+                (
+                    entry.file_name().into_string().unwrap(),
+                    std::fs::read_to_string(entry.path()).unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn test_interrupted_extraction_keeps_installed_driver() {
+        let output_directory = tempfile::tempdir().unwrap();
+        let mut archive = make_tar_gz(&["geckodriver", "LICENSE"]).into_inner();
+
+        std::fs::write(output_directory.path().join("geckodriver"), "old").unwrap();
+        archive.truncate(archive.len() / 2);
+
+        let result = extract_tar_gz(
+            Cursor::new(archive),
+            output_directory.path(),
+            &["geckodriver"],
+            &|_| Ok(()),
+        );
+
+        assert!(result.is_err());
+        assert_eq!(
+            vec![(String::from("geckodriver"), String::from("old"))],
+            read_output_directory(output_directory.path())
+        );
+    }
+
+    #[test]
+    fn test_failed_verification_keeps_installed_driver() {
+        let output_directory = tempfile::tempdir().unwrap();
+
+        std::fs::write(output_directory.path().join("chromedriver"), "old").unwrap();
+
+        let result = extract_archive(
+            make_zip(&["chromedriver-linux64/chromedriver"]),
+            output_directory.path(),
+            &["chromedriver"],
+            &|driver_directory| {
+                assert!(driver_directory.join("chromedriver").is_file());
+                Err(Error::DriverVerificationFailed(String::from("test")))
+            },
+        );
+
+        assert!(matches!(result, Err(Error::DriverVerificationFailed(_))));
+        assert_eq!(
+            vec![(String::from("chromedriver"), String::from("old"))],
+            read_output_directory(output_directory.path())
+        );
+    }
+
+    #[test]
+    fn test_replace_path_replaces_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let source = directory.path().join("new");
+        let target = directory.path().join("Driver_Notes");
+
+        std::fs::create_dir(&source).unwrap();
+        std::fs::write(source.join("credits.html"), "new").unwrap();
+        std::fs::create_dir(&target).unwrap();
+        std::fs::write(target.join("credits.html"), "old").unwrap();
+
+        replace_path(&source, &target).unwrap();
+
+        assert_eq!(
+            "new",
+            std::fs::read_to_string(target.join("credits.html")).unwrap()
+        );
+        assert_eq!(
+            vec![(String::from("Driver_Notes"), String::new())],
+            read_output_directory(directory.path())
+        );
+    }
+
+    #[test_case("https://example.com/geckodriver-linux64.tar.gz", make_zip(&["a"]), true ; "tar.gz extension")]
+    #[test_case("https://example.com/download", make_tar_gz(&["a"]), true ; "gzip content")]
+    #[test_case("https://example.com/chromedriver_linux64.zip", make_zip(&["a"]), false ; "zip")]
+    fn test_is_tar_gz(url: &str, mut archive: Cursor<Vec<u8>>, expected: bool) {
+        assert_eq!(expected, is_tar_gz(url, &mut archive).unwrap());
+        assert_eq!(0, archive.position());
+    }
+
+    #[test_case(make_zip(&["a"]).into_inner(), &ZIP_MAGIC, None ; "zip")]
+    #[test_case(make_tar_gz(&["a"]).into_inner(), &GZIP_MAGIC, None ; "gzip")]
+    #[test_case(b"<html>\n  <title>Sign in</title>\n</html>".to_vec(), &ZIP_MAGIC, Some("'<html> <title>Sign in</title> </html>'") ; "html page")]
+    #[test_case(Vec::new(), &ZIP_MAGIC, Some("an empty response") ; "empty")]
+    #[test_case(make_zip(&["a"]).into_inner(), &GZIP_MAGIC, Some("'PK") ; "other format")]
+    fn test_check_archive_content(content: Vec<u8>, magic: &[u8], expected: Option<&str>) {
+        let mut reader = Cursor::new(content);
+        let result = check_archive_content(&mut reader, magic);
+
+        match expected {
+            None => assert!(result.is_ok()),
+            Some(expected) => assert!(
+                matches!(result, Err(Error::UnexpectedContent(snippet)) if snippet.starts_with(expected))
+            ),
+        }
+        assert_eq!(0, reader.position());
+    }
+
+    #[test]
+    fn test_download_and_extract_unexpected_content() {
+        let url = test_server::start(|_| (200, b"<html>Captive portal</html>".to_vec()));
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let output_directory = tempfile::tempdir().unwrap();
+        let result = download_and_extract(
+            &client,
+            &format!("{}/chromedriver-linux64.zip", url),
+            output_directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions::default(),
+        );
+
+        assert!(
+            matches!(result, Err(Error::UnexpectedContent(snippet)) if snippet == "'<html>Captive portal</html>'")
+        );
+    }
+
+    #[test]
+    fn test_download_and_extract() {
+        let archive = make_zip(&["chromedriver-linux64/chromedriver"]).into_inner();
+        let url = test_server::start(move |_| (200, archive.clone()));
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let output_directory = tempfile::tempdir().unwrap();
+
+        download_and_extract(
+            &client,
+            &format!("{}/chromedriver-linux64.zip", url),
+            output_directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output_directory.path().join("chromedriver").is_file());
+    }
+
+    /// The first response is interrupted in the middle of the archive, the next ones are
+    /// answered by `resume` with the Range and If-Range headers of the request.
+    fn start_interrupted_server(
+        headers: &'static [(&'static str, &'static str)],
+        resume: fn(Option<&str>, Option<&str>, &[u8]) -> test_server::Response,
+    ) -> String {
+        let archive = make_zip(&["chromedriver-linux64/chromedriver"]).into_inner();
+        let mut is_first = true;
+
+        test_server::start_with_handler(move |request| {
+            if !is_first {
+                return resume(
+                    request.header("range"),
+                    request.header("if-range"),
+                    &archive,
+                );
+            }
+
+            let mut response_headers: Vec<_> = headers
+                .iter()
+                .map(|(name, value)| (*name, value.to_string()))
+                .collect();
+
+            response_headers.push(("Content-Length", archive.len().to_string()));
+            is_first = false;
+            (200, response_headers, archive[..archive.len() / 2].to_vec())
+        })
+    }
+
+    fn download_from(url: &str) -> Result<tempfile::TempDir, Error> {
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let output_directory = tempfile::tempdir().unwrap();
+
+        download_and_extract(
+            &client,
+            &format!("{}/chromedriver-linux64.zip", url),
+            output_directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions::default(),
+        )
+        .map(|_| output_directory)
+    }
+
+    #[test]
+    fn test_download_and_extract_resumes() {
+        let url = start_interrupted_server(
+            &[("Accept-Ranges", "bytes"), ("ETag", "\"v1\"")],
+            |range, if_range, archive| {
+                let start = archive.len() / 2;
+
+                if range != Some(&format!("bytes={}-", start)) || if_range != Some("\"v1\"") {
+                    return (400, Vec::new(), Vec::new());
+                }
+
+                let content_range =
+                    format!("bytes {}-{}/{}", start, archive.len() - 1, archive.len());
+
+                (
+                    206,
+                    vec![("Content-Range", content_range)],
+                    archive[start..].to_vec(),
+                )
+            },
+        );
+        let output_directory = download_from(&url).unwrap();
+
+        assert!(output_directory.path().join("chromedriver").is_file());
+    }
+
+    #[test_case(&[] ; "ranges not accepted")]
+    #[test_case(&[("Accept-Ranges", "bytes"), ("ETag", "\"v1\"")] ; "download changed")]
+    fn test_download_and_extract_restarts(headers: &'static [(&'static str, &'static str)]) {
+        // The whole archive is sent again when the range is not requested, or when If-Range
+        // does not match because the download changed.
+        let url =
+            start_interrupted_server(headers, |_, _, archive| (200, Vec::new(), archive.to_vec()));
+        let output_directory = download_from(&url).unwrap();
+
+        assert!(output_directory.path().join("chromedriver").is_file());
+    }
+
+    #[test]
+    fn test_download_and_extract_driver_name() {
+        let archive = make_zip(&["chromedriver-linux64/chromedriver"]).into_inner();
+        let url = test_server::start(move |_| (200, archive.clone()));
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let output_directory = tempfile::tempdir().unwrap();
+
+        download_and_extract(
+            &client,
+            &format!("{}/chromedriver-linux64.zip", url),
+            output_directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions {
+                driver_name: Some("chromedriver-120"),
+                ..DownloadOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(output_directory.path().join("chromedriver-120").is_file());
+        assert!(!output_directory.path().join("chromedriver").exists());
+    }
+
+    #[test]
+    fn test_download_and_extract_too_large() {
+        let archive = make_zip(&["chromedriver"]).into_inner();
+        let url = test_server::start(move |_| (200, archive.clone()));
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let output_directory = tempfile::tempdir().unwrap();
+        let result = download_and_extract(
+            &client,
+            &format!("{}/chromedriver_linux64.zip", url),
+            output_directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions {
+                max_download_size: Some(10),
+                ..DownloadOptions::default()
+            },
+        );
+
+        assert!(matches!(result, Err(Error::DownloadTooLarge(10))));
+        assert!(!output_directory.path().join("chromedriver").exists());
+    }
+
+    #[test_case(false, None, UpdateStatus::NotInstalled { to: Version::new(120, 0, 6099, 109) } ; "not installed")]
+    #[test_case(true, Some(Version::new(120, 0, 6099, 109)), UpdateStatus::UpToDate { version: Version::new(120, 0, 6099, 109) } ; "up to date")]
+    #[test_case(true, Some(Version::new(120, 0, 6099, 216)), UpdateStatus::UpToDate { version: Version::new(120, 0, 6099, 216) } ; "newer")]
+    #[test_case(true, Some(Version::new(119, 0, 6045, 105)), UpdateStatus::UpdateAvailable { from: Some(Version::new(119, 0, 6045, 105)), to: Version::new(120, 0, 6099, 109) } ; "older")]
+    #[test_case(true, None, UpdateStatus::UpdateAvailable { from: None, to: Version::new(120, 0, 6099, 109) } ; "broken")]
+    fn test_get_update_status(
+        is_installed: bool,
+        current_version: Option<Version>,
+        expected: UpdateStatus,
+    ) {
+        assert_eq!(
+            expected,
+            get_update_status(
+                is_installed,
+                current_version,
+                Version::new(120, 0, 6099, 109)
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_update() {
+        use crate::{check_update, EnsureOptions};
+        use std::os::unix::fs::PermissionsExt;
+
+        let output_directory = tempfile::tempdir().unwrap();
+        let driver_path = output_directory.path().join("msedgedriver");
+        let options = EnsureOptions {
+            browser: Browser::Edge,
+            browser_version: Some(Version::new(120, 0, 2210, 91)),
+            ..EnsureOptions::default()
+        };
+        let check = || check_update(None, output_directory.path(), Platform::Linux, &options);
+
+        assert_eq!(
+            UpdateStatus::NotInstalled {
+                to: Version::new(120, 0, 2210, 91)
+            },
+            check().unwrap()
+        );
+
+        std::fs::write(
+            &driver_path,
+            "#!/bin/sh\necho 'Microsoft Edge WebDriver 120.0.2210.91 (abc)'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(
+            UpdateStatus::UpToDate {
+                version: Version::new(120, 0, 2210, 91)
+            },
+            check().unwrap()
+        );
+    }
+
+    #[test_case(&["chromedriver"], &["chromedriver"] ; "legacy")]
+    #[test_case(&["chromedriver-linux64/", "chromedriver-linux64/chromedriver", "chromedriver-linux64/LICENSE.chromedriver"], &["LICENSE.chromedriver", "chromedriver"] ; "chrome for testing")]
+    #[test_case(&["geckodriver/lib/a.so", "geckodriver/lib/b.so", "geckodriver/geckodriver"], &["geckodriver", "lib"] ; "nested directory")]
+    #[test_case(&["chromedriver", "lib/a.so"], &["chromedriver", "lib"] ; "several roots")]
+    fn test_get_installed_names(paths: &[&str], expected: &[&str]) {
+        let paths = paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+        assert_eq!(expected, get_installed_names(&paths));
+    }
+
+    #[test_case(false ; "zip")]
+    #[test_case(true ; "tar gz")]
+    fn test_preview_extraction(is_tar_gz: bool) {
+        let files = [
+            "chromedriver-linux64/chromedriver",
+            "chromedriver-linux64/LICENSE.chromedriver",
+        ];
+        let (archive, extension) = match is_tar_gz {
+            true => (make_tar_gz(&files).into_inner(), "tar.gz"),
+            false => (make_zip(&files).into_inner(), "zip"),
+        };
+        let url = test_server::start(move |_| (200, archive.clone()));
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let output_directory = tempfile::tempdir().unwrap();
+
+        std::fs::write(output_directory.path().join("LICENSE.chromedriver"), "").unwrap();
+
+        let planned_files = preview_extraction(
+            &client,
+            &format!("{}/chromedriver-linux64.{}", url, extension),
+            output_directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions {
+                driver_name: Some("chromedriver-120"),
+                ..DownloadOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                PlannedFile {
+                    name: String::from("LICENSE.chromedriver"),
+                    overwrite: true
+                },
+                PlannedFile {
+                    name: String::from("chromedriver-120"),
+                    overwrite: false
+                },
+            ],
+            planned_files
+        );
+        assert_eq!(
+            1,
+            std::fs::read_dir(output_directory.path()).unwrap().count()
+        );
+    }
+
+    #[test]
+    fn test_download_archive() {
+        let archive = make_zip(&["chromedriver"]).into_inner();
+        let expected = archive.clone();
+        let url = test_server::start(move |_| (200, archive.clone()));
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let archive_path = directory.path().join("archives/chromedriver_linux64.zip");
+        let size = download_archive(
+            &client,
+            &format!("{}/chromedriver_linux64.zip", url),
+            &archive_path,
+            &DownloadOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(expected.len() as u64, size);
+        assert_eq!(expected, std::fs::read(&archive_path).unwrap());
+    }
+
+    #[test]
+    fn test_download_archive_checksum_mismatch() {
+        let archive = make_zip(&["chromedriver"]).into_inner();
+        let url = test_server::start(move |_| (200, archive.clone()));
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let archive_path = directory.path().join("chromedriver_linux64.zip");
+
+        std::fs::write(&archive_path, "previous").unwrap();
+
+        let result = download_archive(
+            &client,
+            &format!("{}/chromedriver_linux64.zip", url),
+            &archive_path,
+            &DownloadOptions {
+                expected_sha256: Some("0000"),
+                ..DownloadOptions::default()
+            },
+        );
+
+        assert!(matches!(result, Err(Error::ChecksumMismatch(_, _))));
+        assert_eq!("previous", std::fs::read_to_string(&archive_path).unwrap());
+        assert_eq!(1, std::fs::read_dir(directory.path()).unwrap().count());
+    }
+
+    #[test_case(&[b"hello"], true ; "at the limit")]
+    #[test_case(&[b"hello", b"!"], false ; "above the limit")]
+    #[test_case(&[b"hello world"], false ; "single write above the limit")]
+    fn test_size_limited_writer(writes: &[&[u8]], expected: bool) {
+        let mut writer = SizeLimitedWriter {
+            inner: Vec::new(),
+            written: 0,
+            max_size: Some(5),
+        };
+        let result = writes.iter().try_for_each(|buf| writer.write_all(buf));
+
+        assert_eq!(expected, result.is_ok());
+        assert_eq!(
+            expected,
+            check_download_size(Some(writer.written), writer.max_size).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_download_and_extract_creates_output_directory() {
+        let archive = make_zip(&["chromedriver"]).into_inner();
+        let url = test_server::start(move |_| (200, archive.clone()));
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let output_directory = directory.path().join("drivers").join("chrome");
+
+        download_and_extract(
+            &client,
+            &format!("{}/chromedriver_linux64.zip", url),
+            &output_directory,
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions::default(),
+        )
+        .unwrap();
+
+        assert!(output_directory.join("chromedriver").is_file());
+    }
+
+    #[test]
+    fn test_download_and_extract_output_directory_is_file() {
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let result = download_and_extract(
+            &client,
+            "http://127.0.0.1:9/chromedriver_linux64.zip",
+            file.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions::default(),
+        );
+
+        assert!(matches!(result, Err(Error::InvalidOutputDirectory(_, _))));
+    }
+
+    #[test]
+    fn test_check_output_directory() {
+        let directory = tempfile::tempdir().unwrap();
+        let output_directory = directory.path().join("drivers").join("chrome");
+
+        check_output_directory(&output_directory).unwrap();
+
+        assert_eq!(0, std::fs::read_dir(&output_directory).unwrap().count());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_archive_sets_executable_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let output_directory = tempfile::tempdir().unwrap();
+
+        extract_archive(
+            make_zip(&["chromedriver-linux64/chromedriver"]),
+            output_directory.path(),
+            &["chromedriver"],
+            &|_| Ok(()),
+        )
+        .unwrap();
+
+        let metadata = std::fs::metadata(output_directory.path().join("chromedriver")).unwrap();
+
+        assert_eq!(0o111, metadata.permissions().mode() & 0o111);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_archive_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let files = [
+            ("chromedriver", 0o750, 0o750),
+            ("LICENSE.chromedriver", 0o600, 0o600),
+            ("THIRD_PARTY_NOTICES.chromedriver", 0o4755, 0o755),
+        ];
+
+        for (file, mode, _) in files {
+            writer
+                .start_file(
+                    format!("chromedriver-linux64/{}", file),
+                    zip::write::FileOptions::default().unix_permissions(mode),
+                )
+                .unwrap();
+            writer.write_all(file.as_bytes()).unwrap();
+        }
+
+        writer.start_file("../outside", Default::default()).unwrap();
+
+        let mut archive = writer.finish().unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let output_directory = directory.path().join("drivers");
+
+        archive.set_position(0);
+        std::fs::create_dir(&output_directory).unwrap();
+        extract_archive(archive, &output_directory, &["chromedriver"], &|_| Ok(())).unwrap();
+
+        for (file, _, expected) in files {
+            let metadata = std::fs::metadata(output_directory.join(file)).unwrap();
+
+            assert_eq!(expected, metadata.permissions().mode() & 0o7777, "{}", file);
+        }
+
+        assert!(!directory.path().join("outside").exists());
+    }
+
+    #[test_case(Version::new(114, 0, 5735, 90), DEFAULT_BASE_URL, "https://chromedriver.storage.googleapis.com/114.0.5735.90/chromedriver_linux64.zip" ; "legacy")]
+    #[test_case(Version::new(114, 0, 5735, 90), "https://mirror.example.com/chromedriver/", "https://mirror.example.com/chromedriver/114.0.5735.90/chromedriver_linux64.zip" ; "legacy mirror")]
+    #[test_case(Version::new(120, 0, 6099, 109), DEFAULT_BASE_URL, "https://storage.googleapis.com/chrome-for-testing-public/120.0.6099.109/linux64/chromedriver-linux64.zip" ; "chrome for testing")]
+    #[test_case(Version::new(120, 0, 6099, 109), "https://mirror.example.com/", "https://mirror.example.com/120.0.6099.109/linux64/chromedriver-linux64.zip" ; "chrome for testing mirror")]
+    fn test_get_download_url(version: Version, base_url: &str, expected: &str) {
+        assert_eq!(
+            expected,
+            get_download_url(version, Browser::Chrome, Platform::Linux, base_url)
+        );
+    }
+
+    #[test_case(Version::new(114, 0, 5735, 90), DEFAULT_BASE_URL ; "legacy")]
+    #[test_case(Version::new(120, 0, 6099, 109), "https://mirror.example.com" ; "chrome for testing mirror")]
+    fn test_resolve_download_url_from_layout(version: Version, base_url: &str) {
+        use crate::resolve_download_url;
+
+        // Nothing is listed for the legacy drivers and the mirrors, no request is sent.
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+
+        assert_eq!(
+            get_download_url(version, Browser::Chrome, Platform::Linux, base_url),
+            resolve_download_url(&client, version, Browser::Chrome, Platform::Linux, base_url)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_download_url_linux_arm64() {
+        assert_eq!(
+            "https://storage.googleapis.com/chrome-for-testing-public/120.0.6099.109/linux-arm64/chromedriver-linux-arm64.zip",
+            get_download_url(
+                Version::new(120, 0, 6099, 109),
+                Browser::Chrome,
+                Platform::LinuxArm64,
+                DEFAULT_BASE_URL
+            )
+        );
+    }
+
+    #[test_case(Version::new(114, 0, 5735, 90), "https://chromedriver.storage.googleapis.com/114.0.5735.90/chromedriver_linux-arm64.zip" ; "legacy")]
+    #[test_case(Version::new(120, 0, 6099, 109), "https://storage.googleapis.com/chrome-for-testing-public/120.0.6099.109/linux-arm64/chromedriver-linux-arm64.zip" ; "chrome for testing")]
+    fn test_get_chromedriver_download_url(version: Version, expected: &str) {
+        use crate::get_chromedriver_download_url;
+
+        assert_eq!(
+            expected,
+            get_chromedriver_download_url(version, "linux-arm64", DEFAULT_BASE_URL)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test_case("echo 'ChromeDriver 120.0.6099.109 (abc)'", true ; "matching version")]
+    #[test_case("echo 'ChromeDriver 119.0.6045.105 (abc)'", false ; "other version")]
+    #[test_case("exit 1", false ; "not working")]
+    fn test_verify_driver(script: &str, expected: bool) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let driver_path = directory.path().join("chromedriver");
+
+        std::fs::write(&driver_path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = verify_driver(
+            directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            Version::new(120, 0, 6099, 109),
+        );
+
+        assert_eq!(expected, result.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test_case("echo 'ChromeDriver 114.0.5735.90 (abc)'", true ; "up to date")]
+    #[test_case("echo 'ChromeDriver 113.0.5672.63 (abc)'", false ; "outdated")]
+    fn test_ensure_driver(script: &str, expected: bool) {
+        use crate::{ensure_driver, EnsureOptions};
+        use std::os::unix::fs::PermissionsExt;
+
+        // Only the lookup is served, downloading the driver fails.
+        let url =
+            test_server::start_with_routes(&[("/LATEST_RELEASE_114.0.5735", "114.0.5735.90")]);
+        let directory = tempfile::tempdir().unwrap();
+        let driver_path = directory.path().join("chromedriver");
+
+        std::fs::write(&driver_path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = ensure_driver(
+            None,
+            directory.path(),
+            &EnsureOptions {
+                browser_version: Some(Version::new(114, 0, 5735, 133)),
+                base_url: url,
+                http: HttpOptions {
+                    max_retries: 0,
+                    ..HttpOptions::default()
+                },
+                ..EnsureOptions::default()
+            },
+        );
+
+        match result {
+            Ok(path) => assert!(expected && path == driver_path),
+            Err(error) => assert!(!expected && matches!(error, Error::NoDriverForVersion(_))),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_get_local_driver_version_at() {
+        use crate::get_local_driver_version_at;
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let driver_path = directory.path().join("chromedriver-120");
+
+        std::fs::write(
+            &driver_path,
+            "#!/bin/sh\necho 'ChromeDriver 120.0.6099.109 (abc)'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = get_local_driver_version_at(&driver_path, Browser::Chrome).unwrap();
+
+        assert_eq!(Some(Version::new(120, 0, 6099, 109)), result);
+        assert_eq!(
+            None,
+            get_local_driver_version_at(&directory.path().join("chromedriver"), Browser::Chrome)
+                .unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test_case("ChromeDriver 120.0.6099.109 (3419140ab665596f21b385ce136419fde0924272-refs/branch-heads/6099@{#1483})", Some("3419140ab665596f21b385ce136419fde0924272") ; "revision")]
+    #[test_case("ChromeDriver 120.0.6099.109", None ; "no revision")]
+    fn test_get_local_driver_version_and_revision(output: &str, expected: Option<&str>) {
+        use crate::get_local_driver_version_and_revision;
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let driver_path = directory.path().join("chromedriver");
+
+        std::fs::write(&driver_path, format!("#!/bin/sh\necho '{}'\n", output)).unwrap();
+        std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = get_local_driver_version_and_revision(
+            directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some((Version::new(120, 0, 6099, 109), expected.map(String::from))),
+            result
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_get_local_driver_version_suffixed() {
+        use crate::get_local_driver_version;
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let broken_path = directory.path().join("chromedriver");
+        let driver_path = directory.path().join("chromedriver-linux64");
+
+        // The standard name is probed first, a driver failing to report its version is skipped.
+        std::fs::write(&broken_path, "#!/bin/sh\necho 'not a driver'\n").unwrap();
+        std::fs::write(
+            &driver_path,
+            "#!/bin/sh\necho 'ChromeDriver 120.0.6099.109 (abc)'\n",
+        )
+        .unwrap();
+
+        for path in [&broken_path, &driver_path] {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let result =
+            get_local_driver_version(directory.path(), Browser::Chrome, Platform::Linux).unwrap();
+
+        assert_eq!(Some(Version::new(120, 0, 6099, 109)), result);
+
+        std::fs::remove_file(&driver_path).unwrap();
+
+        let result = get_local_driver_version(directory.path(), Browser::Chrome, Platform::Linux);
+
+        assert!(matches!(result, Err(Error::ParsingVersionFailed(_))));
+    }
+
+    #[test_case("120.0.6099.109\n", Some(Version::new(120, 0, 6099, 109)) ; "version")]
+    #[test_case("  120.0.6099\r\nbuilt by ci\n", Some(Version::new(120, 0, 6099, 0)) ; "partial version")]
+    #[test_case("Google Chrome 120.0.6099.109\n", None ; "not a version")]
+    #[test_case("", None ; "empty")]
+    fn test_read_browser_version_file(content: &str, expected: Option<Version>) {
+        use crate::read_browser_version_file;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("chrome_version");
+
+        std::fs::write(&path, content).unwrap();
+
+        match (read_browser_version_file(&path), expected) {
+            (Ok(version), Some(expected)) => assert_eq!(expected, version),
+            (Err(Error::ParsingVersionFailed(message)), None) => {
+                assert!(message.contains("chrome_version"), "{}", message)
+            }
+            (result, _) => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_read_browser_version_file_missing() {
+        use crate::read_browser_version_file;
+
+        let directory = tempfile::tempdir().unwrap();
+        let result = read_browser_version_file(&directory.path().join("chrome_version"));
+
+        assert!(matches!(result, Err(Error::CantReadVersionFile(_, _))));
+    }
+
+    #[test_case(r"C:\Program Files\Google\Chrome\Application\chrome.exe", r"name='C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe'" ; "program files")]
+    #[test_case(r"C:\Program Files (x86)\Google\Chrome\Application\chrome.exe", r"name='C:\\Program Files (x86)\\Google\\Chrome\\Application\\chrome.exe'" ; "program files x86")]
+    #[test_case(r"\\?\C:\Users\user\AppData\Local\Google\Chrome\Application\chrome.exe", r"name='C:\\Users\\user\\AppData\\Local\\Google\\Chrome\\Application\\chrome.exe'" ; "long path")]
+    #[test_case(r"\\?\UNC\server\share\chrome.exe", r"name='\\\\server\\share\\chrome.exe'" ; "long unc path")]
+    #[test_case(r"C:\Users\O'Brien\chrome.exe", r"name='C:\\Users\\O\'Brien\\chrome.exe'" ; "quote")]
+    fn test_format_wmic_name_filter(program_path: &str, expected: &str) {
+        assert_eq!(expected, crate::format_wmic_name_filter(program_path));
+    }
+
+    #[test]
+    fn test_get_local_browser_version_not_installed() {
+        use crate::get_local_browser_version;
+
+        let directory = tempfile::tempdir().unwrap();
+        let browser_path = directory.path().join("google-chrome");
+        let result = get_local_browser_version(&browser_path, Browser::Chrome);
+
+        assert!(
+            matches!(result, Err(Error::BrowserNotInstalled(Browser::Chrome, path)) if path == browser_path)
+        );
+    }
+
+    #[test]
+    fn test_get_local_browser_version_directory() {
+        use crate::get_local_browser_version;
+
+        let directory = tempfile::tempdir().unwrap();
+        let result = get_local_browser_version(directory.path(), Browser::Chrome);
+
+        assert!(matches!(result, Err(Error::NotAnExecutable(path)) if path == directory.path()));
+    }
+
+    #[cfg(unix)]
+    #[test_case("echo \"Starting ChromeDriver on port ${1#--port=}\"; echo 'ChromeDriver was started successfully.'; exec sleep 10", true ; "started")]
+    #[test_case("echo 'error while loading shared libraries: libnss3.so' >&2; exit 127", false ; "missing library")]
+    fn test_smoke_test_driver(script: &str, expected: bool) {
+        use crate::smoke_test_driver;
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let driver_path = directory.path().join("chromedriver");
+
+        // Only an ephemeral port is accepted.
+        std::fs::write(
+            &driver_path,
+            format!(
+                "#!/bin/sh\ncase \"$1\" in --port=[1-9]*) ;; *) exit 2 ;; esac\n{}\n",
+                script
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = smoke_test_driver(&driver_path, Browser::Chrome);
+
+        match result {
+            Ok(()) => assert!(expected),
+            Err(Error::SmokeTestFailed(path, reason)) => {
+                assert!(!expected && path == driver_path && reason.contains("libnss3.so"))
+            }
+            Err(error) => panic!("Unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_verify_driver_missing() {
+        let directory = tempfile::tempdir().unwrap();
+        let result = verify_driver(
+            directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            Version::new(120, 0, 6099, 109),
+        );
+
+        assert!(matches!(result, Err(Error::DriverVerificationFailed(_))));
+    }
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test_case(HELLO_SHA256, true ; "matching")]
+    #[test_case(&HELLO_SHA256.to_uppercase(), true ; "matching uppercase")]
+    #[test_case("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855", false ; "mismatching")]
+    fn test_verify_checksum(expected_sha256: &str, expected: bool) {
+        let result = verify_checksum(&mut Cursor::new(b"hello"), expected_sha256);
+
+        assert_eq!(expected, result.is_ok());
+    }
+
+    #[test_case(&[("/LATEST_RELEASE_114.0.5735", "114.0.5735.90")], Some(Version::new(114, 0, 5735, 90)) ; "exact build")]
+    #[test_case(&[("/LATEST_RELEASE_114.0", "114.0.5735.90")], Some(Version::new(114, 0, 5735, 90)) ; "fallback to minor")]
+    #[test_case(&[("/LATEST_RELEASE_114", "114.0.5735.90")], Some(Version::new(114, 0, 5735, 90)) ; "fallback to major")]
+    #[test_case(&[("/?delimiter=/&prefix=", "<ListBucketResult><CommonPrefixes><Prefix>114.0.5735.90/</Prefix></CommonPrefixes></ListBucketResult>")], Some(Version::new(114, 0, 5735, 90)) ; "fallback to the listing")]
+    #[test_case(&[("/?delimiter=/&prefix=", "<ListBucketResult><CommonPrefixes><Prefix>113.0.5672.63/</Prefix></CommonPrefixes></ListBucketResult>")], None ; "not in the listing")]
+    #[test_case(&[], None ; "no driver")]
+    fn test_get_legacy_driver_version(
+        routes: &'static [(&'static str, &'static str)],
+        expected: Option<Version>,
+    ) {
+        let url = test_server::start_with_routes(routes);
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let result = get_legacy_driver_version(&client, &url, Version::new(114, 0, 5735, 110));
+
+        match expected {
+            Some(expected) => assert_eq!(expected, result.unwrap()),
+            None => assert!(matches!(result, Err(Error::NoDriverForVersion(_)))),
+        }
+    }
+
+    #[test_case(404, true ; "not found")]
+    #[test_case(500, false ; "server error")]
+    fn test_get_legacy_driver_version_status(status: u16, expected_no_driver: bool) {
+        let url = test_server::start(move |_| (status, Vec::new()));
+        let client = HttpClient::new(&HttpOptions {
+            max_retries: 0,
+            ..HttpOptions::default()
+        })
+        .unwrap();
+        let result = get_legacy_driver_version(&client, &url, Version::new(114, 0, 5735, 110));
+
+        match result {
+            Err(Error::NoDriverForVersion(_)) => assert!(expected_no_driver),
+            Err(Error::HttpStatus(status, _)) => {
+                assert!(!expected_no_driver);
+                assert_eq!(500, status);
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_get_legacy_driver_version_empty_response() {
+        let url = test_server::start_with_routes(&[("/LATEST_RELEASE_114.0.5735", "")]);
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let result = get_legacy_driver_version(&client, &url, Version::new(114, 0, 5735, 110));
+
+        assert!(
+            matches!(result, Err(Error::EmptyResponse(empty_url)) if empty_url == format!("{}/LATEST_RELEASE_114.0.5735", url))
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Installation of the driver into a directory of the PATH.
+use crate::Error;
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Make the driver at `driver_path` available in `link_directory`, with the same name.
+/// A symbolic link is created on Unix, the driver is copied on Windows since creating symbolic
+/// links requires privileges there. An existing link is replaced, but not a regular file on Unix.
+/// Returns the path of the link.
+pub fn link_driver(driver_path: &Path, link_directory: &Path) -> Result<PathBuf, Error> {
+    let link_path = link_directory.join(driver_path.file_name().unwrap_or_default());
+    let to_error = |error| Error::LinkFailed(link_path.clone(), error);
+    let driver_path = driver_path.canonicalize().map_err(to_error)?;
+
+    create_link(&driver_path, &link_path).map_err(to_error)?;
+    info!(
+        "Linked '{}' to '{}'",
+        link_path.display(),
+        driver_path.display()
+    );
+
+    Ok(link_path)
+}
+
+#[cfg(unix)]
+fn create_link(driver_path: &Path, link_path: &Path) -> std::io::Result<()> {
+    match fs::symlink_metadata(link_path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::remove_file(link_path)?,
+        Ok(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "a file which is not a symbolic link already exists",
+            ))
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => return Err(error),
+    }
+
+    std::os::unix::fs::symlink(driver_path, link_path)
+}
+
+#[cfg(windows)]
+fn create_link(driver_path: &Path, link_path: &Path) -> std::io::Result<()> {
+    fs::copy(driver_path, link_path).map(|_| ())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::link::link_driver;
+    use crate::Error;
+    use std::fs;
+
+    #[test]
+    fn test_link_driver() {
+        let output_directory = tempfile::tempdir().unwrap();
+        let link_directory = tempfile::tempdir().unwrap();
+        let driver_path = output_directory.path().join("chromedriver");
+
+        fs::write(&driver_path, "new").unwrap();
+        std::os::unix::fs::symlink("previous", link_directory.path().join("chromedriver")).unwrap();
+
+        let link_path = link_driver(&driver_path, link_directory.path()).unwrap();
+
+        assert_eq!(
+            driver_path.canonicalize().unwrap(),
+            fs::read_link(&link_path).unwrap()
+        );
+        assert_eq!("new", fs::read_to_string(&link_path).unwrap());
+    }
+
+    #[test]
+    fn test_link_driver_custom_name() {
+        let output_directory = tempfile::tempdir().unwrap();
+        let link_directory = tempfile::tempdir().unwrap();
+        let driver_path = output_directory.path().join("chromedriver_2");
+
+        fs::write(&driver_path, "new").unwrap();
+
+        let link_path = link_driver(&driver_path, link_directory.path()).unwrap();
+
+        assert_eq!(link_directory.path().join("chromedriver_2"), link_path);
+    }
+
+    #[test]
+    fn test_link_driver_keeps_regular_file() {
+        let output_directory = tempfile::tempdir().unwrap();
+        let link_directory = tempfile::tempdir().unwrap();
+        let existing_path = link_directory.path().join("chromedriver");
+
+        let driver_path = output_directory.path().join("chromedriver");
+
+        fs::write(&driver_path, "new").unwrap();
+        fs::write(&existing_path, "existing").unwrap();
+
+        let result = link_driver(&driver_path, link_directory.path());
+
+        assert!(matches!(result, Err(Error::LinkFailed(_, _))));
+        assert_eq!("existing", fs::read_to_string(&existing_path).unwrap());
+    }
+}
@@ -0,0 +1,92 @@
+//! Advisory lock on the output directory, so the runs sharing it install one after the other
+//! instead of replacing the files of each other.
+use crate::Error;
+use log::info;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The file locked in the output directory. It is left there, only its lock matters.
+pub const LOCK_FILE_NAME: &str = ".update_chrome_driver.lock";
+
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Held for the duration of an update. The lock is released when it is dropped, including on
+/// the error paths, and by the system if the process dies.
+#[derive(Debug)]
+pub struct OutputDirectoryLock {
+    _file: File,
+}
+
+/// Lock `output_directory`, which must exist, waiting at most `timeout` for the other runs
+/// holding it.
+pub fn lock_output_directory(
+    output_directory: &Path,
+    timeout: Duration,
+) -> Result<OutputDirectoryLock, Error> {
+    let path = output_directory.join(LOCK_FILE_NAME);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+    let start = Instant::now();
+    let mut is_waiting = false;
+
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(OutputDirectoryLock { _file: file }),
+            Err(TryLockError::WouldBlock) if start.elapsed() < timeout => {
+                if !is_waiting {
+                    info!(
+                        "Waiting for another update of '{}'",
+                        output_directory.display()
+                    );
+                    is_waiting = true;
+                }
+
+                std::thread::sleep(POLL_INTERVAL.min(timeout.saturating_sub(start.elapsed())));
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(Error::LockTimeout(output_directory.to_path_buf(), timeout))
+            }
+            Err(TryLockError::Error(error)) => return Err(error.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lock::lock_output_directory;
+    use crate::Error;
+    use std::time::Duration;
+
+    #[test]
+    fn test_lock_output_directory_times_out() {
+        let directory = tempfile::tempdir().unwrap();
+        let lock = lock_output_directory(directory.path(), Duration::ZERO).unwrap();
+        let result = lock_output_directory(directory.path(), Duration::from_millis(200));
+
+        assert!(matches!(result, Err(Error::LockTimeout(_, _))));
+
+        drop(lock);
+        lock_output_directory(directory.path(), Duration::ZERO).unwrap();
+    }
+
+    #[test]
+    fn test_lock_output_directory_waits() {
+        let directory = tempfile::tempdir().unwrap();
+        let lock = lock_output_directory(directory.path(), Duration::ZERO).unwrap();
+        let path = directory.path().to_path_buf();
+        let waiting = std::thread::spawn(move || {
+            lock_output_directory(&path, Duration::from_secs(10)).map(drop)
+        });
+
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!waiting.is_finished());
+        drop(lock);
+        waiting.join().unwrap().unwrap();
+    }
+}
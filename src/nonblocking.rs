@@ -0,0 +1,393 @@
+//! Asynchronous equivalents of [`crate::get_required_driver_version`] and
+//! [`crate::download_and_extract`], on the async client of reqwest, for the applications
+//! running on tokio. Requires the `async` feature.
+use crate::http::{
+    check_not_empty, get_range_headers, get_resume_validator, get_retry_delay, is_interrupted,
+    is_resumed_at, is_transient, load_certificates,
+};
+use crate::{
+    check_download_size, check_output_directory, chrome_for_testing, extract_downloaded_archive,
+    geckodriver, get_legacy_candidates, parse_legacy_response, progress, storage_listing, Browser,
+    DownloadOptions, Error, HttpOptions, Platform, Version, DEFAULT_USER_AGENT,
+};
+use log::{debug, info, warn};
+use reqwest::header::HeaderMap;
+use reqwest::Response;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Same as [`crate::HttpClient`], except the responses are never cached.
+pub struct AsyncHttpClient {
+    client: reqwest::Client,
+    max_retries: u32,
+    initial_retry_delay: Duration,
+}
+
+impl AsyncHttpClient {
+    /// The `cache_directory` of the options is ignored.
+    pub fn new(options: &HttpOptions) -> Result<Self, Error> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+            .connect_timeout(options.timeout)
+            .timeout(options.timeout)
+            .danger_accept_invalid_certs(options.insecure);
+
+        // An explicit proxy replaces the ones of the environment, except for NO_PROXY.
+        if let Some(proxy) = &options.proxy {
+            builder =
+                builder.proxy(reqwest::Proxy::all(proxy)?.no_proxy(reqwest::NoProxy::from_env()));
+        }
+
+        if let Some(path) = &options.ca_certificate {
+            for certificate in load_certificates(path)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+            max_retries: options.max_retries,
+            initial_retry_delay: Duration::from_millis(500),
+        })
+    }
+
+    /// Send a GET request, retrying with an exponential backoff when the failure is transient.
+    /// Responses with an error status are returned as errors.
+    pub async fn get(&self, url: &str) -> Result<Response, Error> {
+        self.get_with_headers(url, HeaderMap::new()).await
+    }
+
+    async fn get_with_headers(&self, url: &str, headers: HeaderMap) -> Result<Response, Error> {
+        let mut attempt = 0;
+
+        loop {
+            debug!("GET {}", url);
+
+            let result = match self.client.get(url).headers(headers.clone()).send().await {
+                Ok(response) => response.error_for_status(),
+                Err(error) => Err(error),
+            };
+
+            match result {
+                Ok(response) => return Ok(response),
+                Err(error) if attempt < self.max_retries && is_transient(&error) => {
+                    let delay = get_retry_delay(self.initial_retry_delay, attempt);
+
+                    warn!("{}, retrying in {:?}", error, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String, Error> {
+        check_not_empty(url, self.get(url).await?.text().await?)
+    }
+}
+
+/// See [`crate::get_required_driver_version`].
+pub async fn get_required_driver_version(
+    client: &AsyncHttpClient,
+    browser: Browser,
+    chrome_version: Version,
+    base_url: &str,
+) -> Result<Version, Error> {
+    match browser {
+        Browser::Chrome => {}
+        Browser::Edge => return Ok(chrome_version),
+        Browser::Firefox => {
+            let response = client.get_text(geckodriver::RELEASES_URL).await?;
+
+            return geckodriver::parse_releases(&response, chrome_version);
+        }
+    }
+
+    if chrome_version.major >= chrome_for_testing::FIRST_MILESTONE {
+        let response = client
+            .get_text(&chrome_for_testing::get_endpoint_url(
+                base_url,
+                chrome_for_testing::LATEST_VERSIONS_PER_MILESTONE_ENDPOINT,
+            ))
+            .await?;
+
+        return chrome_for_testing::parse_latest_versions_per_milestone(&response, chrome_version);
+    }
+
+    get_legacy_driver_version(client, base_url.trim_end_matches('/'), chrome_version).await
+}
+
+async fn get_legacy_driver_version(
+    client: &AsyncHttpClient,
+    base_url: &str,
+    chrome_version: Version,
+) -> Result<Version, Error> {
+    for candidate in get_legacy_candidates(chrome_version) {
+        let url = format!("{}/LATEST_RELEASE_{}", base_url, candidate);
+        let response = match client.get_text(&url).await {
+            Ok(response) => response,
+            Err(error) if error.is_not_found() => {
+                debug!("No release published for {}", candidate);
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+
+        return parse_legacy_response(&candidate, &response);
+    }
+
+    debug!("No latest release published, looking in the listing of the storage");
+
+    let mut versions = Vec::new();
+    let mut marker = None;
+
+    loop {
+        let mut url = format!("{}/?delimiter=/&prefix=", base_url);
+
+        if let Some(marker) = &marker {
+            url.push_str(&format!("&marker={}", marker));
+        }
+
+        let listing = match client.get_text(&url).await {
+            Ok(response) => storage_listing::parse_listing(&response)?,
+            Err(error) if error.is_not_found() => break,
+            Err(error) => return Err(error),
+        };
+
+        versions.extend(listing.versions);
+
+        match listing.next_marker {
+            Some(next_marker) if Some(&next_marker) != marker.as_ref() => {
+                marker = Some(next_marker)
+            }
+            _ => break,
+        }
+    }
+
+    storage_listing::select_version(&versions, chrome_version)
+        .ok_or(Error::NoDriverForVersion(chrome_version))
+}
+
+/// See [`crate::download_and_extract`]. The archive is checked and extracted on the blocking
+/// thread pool of tokio.
+pub async fn download_and_extract(
+    client: &AsyncHttpClient,
+    url: &str,
+    output_directory: &Path,
+    browser: Browser,
+    platform: Platform,
+    options: &DownloadOptions<'_>,
+) -> Result<(), Error> {
+    check_output_directory(output_directory)?;
+
+    let mut file = tempfile::tempfile()?;
+    let start = std::time::Instant::now();
+    let mut response = client.get(url).await?;
+
+    check_download_size(response.content_length(), options.max_download_size)?;
+
+    let progress_bar = progress::make_progress_bar(
+        response.content_length(),
+        options.show_progress,
+        options.progress_label,
+    );
+    let mut resume_validator = get_resume_validator(response.headers());
+    let mut size = 0;
+    let mut attempt = 0;
+
+    // Resumed or restarted like the blocking download when reading the body is interrupted.
+    loop {
+        let error = loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    size += chunk.len() as u64;
+                    check_download_size(Some(size), options.max_download_size)?;
+                    progress_bar.wrap_write(&mut file).write_all(&chunk)?;
+                }
+                Ok(None) => break None,
+                Err(error) if attempt < client.max_retries && is_interrupted(&error) => {
+                    break Some(error)
+                }
+                Err(error) => return Err(error.into()),
+            }
+        };
+        let Some(error) = error else {
+            break;
+        };
+        let delay = get_retry_delay(client.initial_retry_delay, attempt);
+
+        attempt += 1;
+        warn!("{}, resuming in {:?}", error, delay);
+        tokio::time::sleep(delay).await;
+        response = match &resume_validator {
+            Some(etag) => {
+                client
+                    .get_with_headers(url, get_range_headers(size, etag.as_deref()))
+                    .await?
+            }
+            None => client.get(url).await?,
+        };
+
+        if !is_resumed_at(response.status(), response.headers(), size) {
+            debug!("Downloading {} again from the beginning", url);
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            progress_bar.set_position(0);
+            resume_validator = get_resume_validator(response.headers());
+            size = 0;
+        }
+    }
+
+    progress_bar.finish_and_clear();
+    info!("{}", progress::format_summary(size, start.elapsed()));
+
+    if let Some(timings) = options.timings {
+        timings.add("download", start.elapsed());
+    }
+
+    let url = url.to_string();
+    let output_directory = output_directory.to_path_buf();
+    let expected_sha256 = options.expected_sha256.map(String::from);
+    let verify_version = options.verify_version;
+    let driver_name = options.driver_name.map(String::from);
+    let start = std::time::Instant::now();
+
+    // Measured as a whole on the blocking thread pool, the verification included.
+    let result = tokio::task::spawn_blocking(move || {
+        let options = DownloadOptions {
+            expected_sha256: expected_sha256.as_deref(),
+            show_progress: false,
+            verify_version,
+            driver_name: driver_name.as_deref(),
+            max_download_size: None,
+            progress_label: None,
+            timings: None,
+        };
+
+        extract_downloaded_archive(file, &url, &output_directory, browser, platform, &options)
+    })
+    .await
+    .map_err(|error| Error::FailedToReadOutput(std::io::Error::other(error)))?;
+
+    if let Some(timings) = options.timings {
+        timings.add("extraction", start.elapsed());
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nonblocking::{download_and_extract, get_legacy_driver_version, AsyncHttpClient};
+    use crate::{test_server, Browser, DownloadOptions, Error, HttpOptions, Platform, Version};
+    use std::io::{Cursor, Write};
+
+    fn make_client() -> AsyncHttpClient {
+        let mut client = AsyncHttpClient::new(&HttpOptions::default()).unwrap();
+
+        client.initial_retry_delay = std::time::Duration::from_millis(1);
+        client
+    }
+
+    #[tokio::test]
+    async fn test_get_retries() {
+        let url = test_server::start_with_statuses(&[503, 200]);
+        let response = make_client().get(&url).await.unwrap();
+
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    #[tokio::test]
+    async fn test_get_legacy_driver_version() {
+        let url = test_server::start_with_routes(&[("/LATEST_RELEASE_114.0", "114.0.5735.90")]);
+        let result =
+            get_legacy_driver_version(&make_client(), &url, Version::new(114, 0, 5735, 110)).await;
+
+        assert_eq!(Version::new(114, 0, 5735, 90), result.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_legacy_driver_version_not_found() {
+        let url = test_server::start_with_routes(&[]);
+        let result =
+            get_legacy_driver_version(&make_client(), &url, Version::new(114, 0, 5735, 110)).await;
+
+        assert!(matches!(result, Err(Error::NoDriverForVersion(_))));
+    }
+
+    #[tokio::test]
+    async fn test_download_and_extract() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+        writer
+            .start_file("chromedriver-linux64/chromedriver", Default::default())
+            .unwrap();
+        writer.write_all(b"chromedriver").unwrap();
+
+        let archive = writer.finish().unwrap().into_inner();
+        let url = test_server::start(move |_| (200, archive.clone()));
+        let output_directory = tempfile::tempdir().unwrap();
+
+        download_and_extract(
+            &make_client(),
+            &format!("{}/chromedriver-linux64.zip", url),
+            output_directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(output_directory.path().join("chromedriver").is_file());
+    }
+
+    #[tokio::test]
+    async fn test_download_and_extract_resumes() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+        writer
+            .start_file("chromedriver", Default::default())
+            .unwrap();
+        writer.write_all(b"chromedriver").unwrap();
+
+        let archive = writer.finish().unwrap().into_inner();
+        let start = archive.len() / 2;
+        let url = test_server::start_with_handler(move |request| match request.header("range") {
+            // The first response is interrupted in the middle of the archive.
+            None => (
+                200,
+                vec![
+                    ("Accept-Ranges", String::from("bytes")),
+                    ("Content-Length", archive.len().to_string()),
+                ],
+                archive[..start].to_vec(),
+            ),
+            Some(range) if range == format!("bytes={}-", start) => (
+                206,
+                vec![(
+                    "Content-Range",
+                    format!("bytes {}-{}/{}", start, archive.len() - 1, archive.len()),
+                )],
+                archive[start..].to_vec(),
+            ),
+            Some(_) => (400, Vec::new(), Vec::new()),
+        });
+        let output_directory = tempfile::tempdir().unwrap();
+
+        download_and_extract(
+            &make_client(),
+            &format!("{}/chromedriver-linux64.zip", url),
+            output_directory.path(),
+            Browser::Chrome,
+            Platform::Linux,
+            &DownloadOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(output_directory.path().join("chromedriver").is_file());
+    }
+}
@@ -1,8 +1,8 @@
 use crate::Version;
 use nom::bytes::complete::tag;
-use nom::character::complete::{char, digit1, space0};
-use nom::combinator::map_res;
-use nom::sequence::tuple;
+use nom::character::complete::{char, digit1, hex_digit1, multispace0, space0};
+use nom::combinator::{map_res, opt};
+use nom::sequence::{preceded, tuple};
 use nom::IResult;
 
 fn from_dec(input: &str) -> Result<u32, std::num::ParseIntError> {
@@ -37,41 +37,186 @@ pub fn parse_version_numbers(input: &str) -> IResult<&str, Version> {
     ))
 }
 
-pub fn parse_version_output<'a>(input: &'a str, application: &'a str) -> IResult<&'a str, Version> {
-    let (input, _) = tag(application)(input)?;
+/// Parse the version numbers, the patch number is optional and defaults to 0.
+/// Example: `120.0.6099` or `120.0.6099.109`
+pub fn parse_partial_version_numbers(input: &str) -> IResult<&str, Version> {
+    let (input, (major, _, minor, _, build, patch)) = tuple((
+        parse_dec,
+        char('.'),
+        parse_dec,
+        char('.'),
+        parse_dec,
+        opt(preceded(char('.'), parse_dec)),
+    ))(input)?;
+
+    Ok((
+        input,
+        Version {
+            major,
+            minor,
+            build,
+            patch: patch.unwrap_or(0),
+        },
+    ))
+}
+
+/// Parse the version numbers of a release where the build number is optional and defaults
+/// to 0, the patch number is always 0. Any suffix like `esr` is left unparsed.
+/// Example: `120.0`, `120.0.1` or `115.5.0esr`
+pub fn parse_release_version_numbers(input: &str) -> IResult<&str, Version> {
+    let (input, (major, _, minor, build)) = tuple((
+        parse_dec,
+        char('.'),
+        parse_dec,
+        opt(preceded(char('.'), parse_dec)),
+    ))(input)?;
+
+    Ok((input, Version::new(major, minor, build.unwrap_or(0), 0)))
+}
+
+/// Parse the name of the application, the surrounding whitespace is ignored.
+fn parse_application<'a>(input: &'a str, application: &'a str) -> IResult<&'a str, &'a str> {
+    let (input, _) = multispace0(input)?;
+    let (input, application) = tag(application)(input)?;
     let (input, _) = space0(input)?;
 
+    Ok((input, application))
+}
+
+/// Parse the version following the name of the application, what follows the version
+/// numbers like a channel name or a line ending is ignored.
+pub fn parse_version_output<'a>(input: &'a str, application: &'a str) -> IResult<&'a str, Version> {
+    let (input, _) = parse_application(input, application)?;
+
     parse_version_numbers(input)
 }
 
+/// Like [`parse_version_output`] but some browser builds omit the patch number.
+#[cfg(not(target_os = "windows"))]
+pub fn parse_browser_version_output<'a>(
+    input: &'a str,
+    application: &'a str,
+) -> IResult<&'a str, Version> {
+    let (input, _) = parse_application(input, application)?;
+
+    parse_partial_version_numbers(input)
+}
+
 /// Parse the version in the output of the command `chromedriver --version`.
 /// Example: `ChromeDriver 89.0.4389.23 (61b08ee2c50024bab004e48d2b1b083cdbdac579-refs/branch-heads/4389@{#294})`
 pub fn parse_chromedriver_version_output(input: &str) -> IResult<&str, Version> {
     parse_version_output(input, "ChromeDriver")
 }
 
+/// Parse the hash of the commit the driver was built from, which follows its version.
+/// Example: ` (61b08ee2c50024bab004e48d2b1b083cdbdac579-refs/branch-heads/4389@{#294})`
+pub fn parse_driver_revision(input: &str) -> IResult<&str, &str> {
+    preceded(tuple((space0, char('('))), hex_digit1)(input)
+}
+
+/// Parse the version in the output of the command `msedgedriver --version`.
+/// Example: `Microsoft Edge WebDriver 120.0.2210.91 (f469ce7d1b5b2a5b2ee7ee5423ee0d94a9d1e4c3)`
+pub fn parse_edgedriver_version_output(input: &str) -> IResult<&str, Version> {
+    parse_version_output(input, "Microsoft Edge WebDriver")
+}
+
+/// Parse the version in the output of the command `geckodriver --version`.
+/// Example: `geckodriver 0.34.0 (c44f0d09630a 2024-01-02 15:36 +0000)`
+pub fn parse_geckodriver_version_output(input: &str) -> IResult<&str, Version> {
+    let (input, _) = parse_application(input, "geckodriver")?;
+
+    parse_release_version_numbers(input)
+}
+
+/// Parse the version in the output of the command `firefox --version`, unlike Chrome
+/// Firefox honors this flag on Windows too.
+/// Example: `Mozilla Firefox 120.0.1`
+pub fn parse_firefox_version_output(input: &str) -> IResult<&str, Version> {
+    let (input, _) = parse_application(input, "Mozilla Firefox")?;
+
+    parse_release_version_numbers(input)
+}
+
+/// Parse the version in the output of the command `--version` of Chromium based browsers.
+/// Brave reports its own version first, the version of Chromium follows it.
+/// Examples:
+/// - `Google Chrome 109.0.5414.87`
+/// - `Chromium 120.0.6099.109 built on Debian 12.4, running on Debian 12.4`
+/// - `Brave Browser 1.61.109 Chromium: 120.0.6099.217`
 #[cfg(not(target_os = "windows"))]
 pub fn parse_chromium_version_output(input: &str) -> IResult<&str, Version> {
-    parse_version_output(input, "Google Chrome")
+    use nom::branch::alt;
+    use nom::bytes::complete::take_until;
+
+    alt((
+        |input| parse_browser_version_output(input, "Google Chrome for Testing"),
+        |input| parse_browser_version_output(input, "Google Chrome"),
+        |input| parse_browser_version_output(input, "Chromium"),
+        |input| {
+            let (input, _) = parse_application(input, "Brave Browser")?;
+            let (input, _) = take_until("Chromium:")(input)?;
+
+            parse_browser_version_output(input, "Chromium:")
+        },
+    ))(input)
+}
+
+/// Parse the version in the output of the command `microsoft-edge --version`.
+/// Example: `Microsoft Edge 120.0.2210.91`
+#[cfg(not(target_os = "windows"))]
+pub fn parse_edge_version_output(input: &str) -> IResult<&str, Version> {
+    parse_browser_version_output(input, "Microsoft Edge")
+}
+
+/// Parse the version line of the output of `flatpak info <app id>`, the release number of the
+/// package that can follow the version is ignored.
+/// Example: `     Version: 120.0.6099.109-1`
+#[cfg(target_os = "linux")]
+pub fn parse_flatpak_info_version(input: &str) -> IResult<&str, Version> {
+    use nom::branch::alt;
+
+    let (input, _) = parse_application(input, "Version:")?;
+
+    alt((parse_partial_version_numbers, parse_release_version_numbers))(input)
 }
 
 #[cfg(target_os = "windows")]
 pub fn parse_wmic_version(input: &str) -> IResult<&str, Version> {
     let (input, _) = tag("\r\r\n\r\r\nVersion=")(input)?;
 
-    parse_version_numbers(input)
+    parse_partial_version_numbers(input)
+}
+
+/// Parse the output of `(Get-Item 'chrome.exe').VersionInfo.ProductVersion`.
+/// Example: `120.0.6099.109\r\n`
+#[cfg(target_os = "windows")]
+pub fn parse_powershell_version(input: &str) -> IResult<&str, Version> {
+    let (input, _) = multispace0(input)?;
+
+    parse_partial_version_numbers(input)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parsers::parse_chromedriver_version_output;
+    #[cfg(target_os = "linux")]
+    use crate::parsers::parse_flatpak_info_version;
+    #[cfg(target_os = "windows")]
+    use crate::parsers::parse_powershell_version;
+    use crate::parsers::{
+        parse_chromedriver_version_output, parse_driver_revision, parse_edgedriver_version_output,
+        parse_firefox_version_output, parse_geckodriver_version_output,
+        parse_partial_version_numbers,
+    };
     #[cfg(not(target_os = "windows"))]
-    use crate::parsers::parse_chromium_version_output;
+    use crate::parsers::{parse_chromium_version_output, parse_edge_version_output};
     use crate::Version;
     use nom::Finish;
     use test_case::test_case;
 
     #[test_case("ChromeDriver 89.0.4389.23 (61b08ee2c50024bab004e48d2b1b083cdbdac579-refs/branch-heads/4389@{#294})", Some(Version::new(89, 0, 4389, 23)) ; "basic")]
+    #[test_case("ChromeDriver 120.0.6099", None ; "three components")]
+    #[test_case("\tChromeDriver 89.0.4389.23", Some(Version::new(89, 0, 4389, 23)) ; "leading tab")]
+    #[test_case("ChromeDriver 89.0.4389.23\r\n", Some(Version::new(89, 0, 4389, 23)) ; "crlf")]
     fn test_parse_driver_version_output(input: &str, expected: Option<Version>) {
         let result = parse_chromedriver_version_output(input)
             .finish()
@@ -81,8 +226,78 @@ mod tests {
         assert_eq!(expected, result);
     }
 
+    #[cfg(target_os = "linux")]
+    #[test_case("     Version: 120.0.6099.109-1", Some(Version::new(120, 0, 6099, 109)) ; "package release")]
+    #[test_case("Version: 120.0.6099.109", Some(Version::new(120, 0, 6099, 109)) ; "no indentation")]
+    #[test_case("     Version: 121.0", Some(Version::new(121, 0, 0, 0)) ; "release version")]
+    #[test_case("      Branch: stable", None ; "other line")]
+    fn test_parse_flatpak_info_version(input: &str, expected: Option<Version>) {
+        let result = parse_flatpak_info_version(input)
+            .finish()
+            .ok()
+            .map(|(_, result)| result);
+
+        assert_eq!(expected, result);
+    }
+
+    #[test_case("ChromeDriver 89.0.4389.23 (61b08ee2c50024bab004e48d2b1b083cdbdac579-refs/branch-heads/4389@{#294})", Some((Version::new(89, 0, 4389, 23), Some("61b08ee2c50024bab004e48d2b1b083cdbdac579"))) ; "basic")]
+    #[test_case("ChromeDriver 120.0.6099.109 (3419140ab665596f21b385ce136419fde0924272-refs/branch-heads/6099@{#1483})\n", Some((Version::new(120, 0, 6099, 109), Some("3419140ab665596f21b385ce136419fde0924272"))) ; "chrome for testing")]
+    #[test_case("ChromeDriver 89.0.4389.23\n", Some((Version::new(89, 0, 4389, 23), None)) ; "no revision")]
+    #[test_case("Microsoft Edge WebDriver 120.0.2210.91", None ; "other driver")]
+    fn test_parse_chromedriver_version_and_revision(
+        input: &str,
+        expected: Option<(Version, Option<&str>)>,
+    ) {
+        let result = parse_chromedriver_version_output(input)
+            .ok()
+            .map(|(input, version)| {
+                (
+                    version,
+                    parse_driver_revision(input)
+                        .ok()
+                        .map(|(_, revision)| revision),
+                )
+            });
+
+        assert_eq!(expected, result);
+    }
+
+    #[test_case("Microsoft Edge WebDriver 120.0.2210.91 (f469ce7d1b5b2a5b2ee7ee5423ee0d94a9d1e4c3)", Some(Version::new(120, 0, 2210, 91)) ; "basic")]
+    #[test_case("ChromeDriver 89.0.4389.23", None ; "chromedriver")]
+    fn test_parse_edgedriver_version_output(input: &str, expected: Option<Version>) {
+        let result = parse_edgedriver_version_output(input)
+            .finish()
+            .ok()
+            .map(|(_, result)| result);
+
+        assert_eq!(expected, result);
+    }
+
+    #[test_case("120.0.6099.109", Some(Version::new(120, 0, 6099, 109)) ; "four components")]
+    #[test_case("120.0.6099", Some(Version::new(120, 0, 6099, 0)) ; "three components")]
+    #[test_case("120.0", None ; "two components")]
+    fn test_parse_partial_version_numbers(input: &str, expected: Option<Version>) {
+        let result = parse_partial_version_numbers(input)
+            .finish()
+            .ok()
+            .map(|(_, result)| result);
+
+        assert_eq!(expected, result);
+    }
+
     #[cfg(not(target_os = "windows"))]
     #[test_case("Google Chrome 109.0.5414.87", Some(Version::new(109, 0, 5414, 87)) ; "basic")]
+    #[test_case("Google Chrome for Testing 120.0.6099.109", Some(Version::new(120, 0, 6099, 109)) ; "chrome for testing")]
+    #[test_case("Chromium 120.0.6099.109 built on Debian 12.4, running on Debian 12.4", Some(Version::new(120, 0, 6099, 109)) ; "chromium")]
+    #[test_case("Chromium 120.0.6099.109 snap", Some(Version::new(120, 0, 6099, 109)) ; "chromium snap")]
+    #[test_case("Brave Browser 1.61.109 Chromium: 120.0.6099.217", Some(Version::new(120, 0, 6099, 217)) ; "brave")]
+    #[test_case("Brave Browser 1.61.109", None ; "brave without chromium version")]
+    #[test_case("Google Chrome 120.0.6099", Some(Version::new(120, 0, 6099, 0)) ; "three components")]
+    #[test_case("\tGoogle Chrome 120.0.6099.109", Some(Version::new(120, 0, 6099, 109)) ; "leading tab")]
+    #[test_case("Google Chrome 120.0.6099.109 \r\n", Some(Version::new(120, 0, 6099, 109)) ; "crlf")]
+    #[test_case("Google Chrome 120.0.6099.109 unknown", Some(Version::new(120, 0, 6099, 109)) ; "trailing channel")]
+    #[test_case("\r\n  Google Chrome   120.0.6099.109 beta\r\n", Some(Version::new(120, 0, 6099, 109)) ; "surrounding whitespace")]
+    #[test_case("Mozilla Firefox 120.0.1", None ; "firefox")]
     fn test_parse_browser_version_output(input: &str, expected: Option<Version>) {
         let result = parse_chromium_version_output(input)
             .finish()
@@ -91,4 +306,56 @@ mod tests {
 
         assert_eq!(expected, result);
     }
+
+    #[test_case("geckodriver 0.34.0 (c44f0d09630a 2024-01-02 15:36 +0000)", Some(Version::new(0, 34, 0, 0)) ; "basic")]
+    #[test_case("ChromeDriver 89.0.4389.23", None ; "chromedriver")]
+    fn test_parse_geckodriver_version_output(input: &str, expected: Option<Version>) {
+        let result = parse_geckodriver_version_output(input)
+            .finish()
+            .ok()
+            .map(|(_, result)| result);
+
+        assert_eq!(expected, result);
+    }
+
+    #[test_case("Mozilla Firefox 120.0.1", Some(Version::new(120, 0, 1, 0)) ; "three components")]
+    #[test_case("Mozilla Firefox 120.0", Some(Version::new(120, 0, 0, 0)) ; "two components")]
+    #[test_case("Mozilla Firefox 115.5.0esr", Some(Version::new(115, 5, 0, 0)) ; "extended support release")]
+    #[test_case("Google Chrome 120.0.6099.109", None ; "chrome")]
+    fn test_parse_firefox_version_output(input: &str, expected: Option<Version>) {
+        let result = parse_firefox_version_output(input)
+            .finish()
+            .ok()
+            .map(|(_, result)| result);
+
+        assert_eq!(expected, result);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test_case("Microsoft Edge 120.0.2210.91", Some(Version::new(120, 0, 2210, 91)) ; "basic")]
+    #[test_case("Microsoft Edge 120.0.2210", Some(Version::new(120, 0, 2210, 0)) ; "three components")]
+    #[test_case("Google Chrome 120.0.6099.109", None ; "chrome")]
+    fn test_parse_edge_version_output(input: &str, expected: Option<Version>) {
+        let result = parse_edge_version_output(input)
+            .finish()
+            .ok()
+            .map(|(_, result)| result);
+
+        assert_eq!(expected, result);
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test_case("120.0.6099.109", Some(Version::new(120, 0, 6099, 109)) ; "basic")]
+    #[test_case("120.0.6099.109\r\n", Some(Version::new(120, 0, 6099, 109)) ; "trailing newline")]
+    #[test_case("\r\n120.0.6099.109\r\n\r\n", Some(Version::new(120, 0, 6099, 109)) ; "surrounding newlines")]
+    #[test_case("120.0.6099\r\n", Some(Version::new(120, 0, 6099, 0)) ; "three components")]
+    #[test_case("", None ; "empty")]
+    fn test_parse_powershell_version(input: &str, expected: Option<Version>) {
+        let result = parse_powershell_version(input)
+            .finish()
+            .ok()
+            .map(|(_, result)| result);
+
+        assert_eq!(expected, result);
+    }
 }
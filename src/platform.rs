@@ -0,0 +1,243 @@
+use crate::Error;
+#[cfg(target_os = "macos")]
+use log::debug;
+use serde::{Deserialize, Deserializer};
+#[cfg(target_os = "macos")]
+use std::path::Path;
+#[cfg(target_os = "macos")]
+use std::process::Command;
+use std::str::FromStr;
+#[cfg(target_os = "macos")]
+use std::sync::OnceLock;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Platform {
+    Windows,
+    Windows64,
+    MacOs,
+    MacOsArm64,
+    Linux,
+    LinuxArm64,
+}
+
+impl Platform {
+    pub const ALL: &'static [Platform] = &[
+        Platform::Windows,
+        Platform::Windows64,
+        Platform::MacOs,
+        Platform::MacOsArm64,
+        Platform::Linux,
+        Platform::LinuxArm64,
+    ];
+
+    /// Detect the platform from a target OS and architecture, as reported by
+    /// [`std::env::consts::OS`] and [`std::env::consts::ARCH`].
+    pub fn detect(os: &str, arch: &str) -> Option<Platform> {
+        match (os, arch) {
+            ("windows", "x86_64") => Some(Platform::Windows64),
+            ("windows", _) => Some(Platform::Windows),
+            ("macos", "aarch64") => Some(Platform::MacOsArm64),
+            ("macos", _) => Some(Platform::MacOs),
+            ("linux", "aarch64") => Some(Platform::LinuxArm64),
+            ("linux", _) => Some(Platform::Linux),
+            _ => None,
+        }
+    }
+
+    /// Like [`Platform::detect`], except an x86_64 process translated by Rosetta gets the
+    /// arm64 driver of the Apple Silicon machine it actually runs on.
+    pub fn detect_native(os: &str, arch: &str, is_translated: bool) -> Option<Platform> {
+        match Platform::detect(os, arch) {
+            Some(Platform::MacOs) if is_translated => Some(Platform::MacOsArm64),
+            platform => platform,
+        }
+    }
+
+    /// The platform this program runs on, `None` on one without a driver like FreeBSD, where
+    /// the drivers of the other platforms can still be downloaded.
+    pub fn current() -> Option<Platform> {
+        Platform::detect_native(
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            is_translated_by_rosetta(),
+        )
+    }
+
+    pub fn get_key(self) -> &'static str {
+        match self {
+            // No 64 bits driver was published before Chrome for Testing.
+            Platform::Windows | Platform::Windows64 => "win32",
+            Platform::MacOs => "mac64",
+            Platform::MacOsArm64 => "mac_arm64",
+            Platform::Linux => "linux64",
+            // Never published before Chrome for Testing, the download is not found.
+            Platform::LinuxArm64 => "linux-arm64",
+        }
+    }
+
+    /// The platform key used by Chrome for Testing, which differs from [`Platform::get_key`] on macOS.
+    pub fn get_chrome_for_testing_key(self) -> &'static str {
+        match self {
+            Platform::Windows => "win32",
+            Platform::Windows64 => "win64",
+            Platform::MacOs => "mac-x64",
+            Platform::MacOsArm64 => "mac-arm64",
+            Platform::Linux => "linux64",
+            Platform::LinuxArm64 => "linux-arm64",
+        }
+    }
+
+    /// The platform key used by Microsoft Edge WebDriver.
+    pub fn get_edge_key(self) -> &'static str {
+        match self {
+            Platform::Windows => "win32",
+            Platform::Windows64 => "win64",
+            Platform::MacOs => "mac64",
+            Platform::MacOsArm64 => "mac64_m1",
+            Platform::Linux => "linux64",
+            // Not published by Microsoft, the download is not found.
+            Platform::LinuxArm64 => "linux-arm64",
+        }
+    }
+
+    /// The platform key used in the names of the geckodriver release assets.
+    pub fn get_geckodriver_key(self) -> &'static str {
+        match self {
+            Platform::Windows => "win32",
+            Platform::Windows64 => "win64",
+            Platform::MacOs => "macos",
+            Platform::MacOsArm64 => "macos-aarch64",
+            Platform::Linux => "linux64",
+            Platform::LinuxArm64 => "linux-aarch64",
+        }
+    }
+
+    pub fn get_chromedriver_executable_name(self) -> &'static str {
+        match self {
+            Platform::Windows | Platform::Windows64 => "chromedriver.exe",
+            Platform::MacOs | Platform::MacOsArm64 => "chromedriver",
+            Platform::Linux | Platform::LinuxArm64 => "chromedriver",
+        }
+    }
+}
+
+/// Parse a platform from its legacy or Chrome for Testing key, e.g. `mac64` or `mac-x64`.
+impl FromStr for Platform {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "windows" | "win32" => Ok(Platform::Windows),
+            "win64" => Ok(Platform::Windows64),
+            "mac64" | "mac-x64" => Ok(Platform::MacOs),
+            "mac-arm64" | "mac_arm64" => Ok(Platform::MacOsArm64),
+            "linux64" => Ok(Platform::Linux),
+            "linux-arm64" => Ok(Platform::LinuxArm64),
+            _ => Err(Error::UnsupportedPlatform(input.to_string())),
+        }
+    }
+}
+
+/// Deserialized from the same keys as [`FromStr`].
+impl<'de> Deserialize<'de> for Platform {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// The current platform, see [`Platform::current`]. Panics on an unsupported platform.
+impl Default for Platform {
+    fn default() -> Platform {
+        Platform::current().expect("Unsupported platform")
+    }
+}
+
+/// True if the process runs under Rosetta on Apple Silicon, where `sysctl.proc_translated`
+/// is 1. It does not exist on the Intel Macs. Queried once, the platform is used often.
+#[cfg(target_os = "macos")]
+fn is_translated_by_rosetta() -> bool {
+    static IS_TRANSLATED: OnceLock<bool> = OnceLock::new();
+
+    *IS_TRANSLATED.get_or_init(|| {
+        let program_path = Path::new("/usr/sbin/sysctl");
+        let mut command = Command::new(program_path);
+
+        command.args(["-n", "sysctl.proc_translated"]);
+
+        match crate::process::get_output(program_path, &mut command) {
+            Ok((stdout, _)) => {
+                let is_translated = String::from_utf8_lossy(&stdout).trim() == "1";
+
+                if is_translated {
+                    debug!("Translated by Rosetta, using the arm64 driver");
+                }
+
+                is_translated
+            }
+            Err(error) => {
+                debug!("Can't know if translated by Rosetta: {}", error);
+                false
+            }
+        }
+    })
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_translated_by_rosetta() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Platform;
+    use test_case::test_case;
+
+    #[test_case("windows", "x86", Some(Platform::Windows) ; "windows 32 bits")]
+    #[test_case("windows", "x86_64", Some(Platform::Windows64) ; "windows 64 bits")]
+    #[test_case("macos", "x86_64", Some(Platform::MacOs) ; "macos intel")]
+    #[test_case("macos", "aarch64", Some(Platform::MacOsArm64) ; "macos apple silicon")]
+    #[test_case("linux", "x86_64", Some(Platform::Linux) ; "linux")]
+    #[test_case("linux", "aarch64", Some(Platform::LinuxArm64) ; "linux arm64")]
+    #[test_case("freebsd", "x86_64", None ; "unsupported")]
+    fn test_detect_platform(os: &str, arch: &str, expected: Option<Platform>) {
+        assert_eq!(expected, Platform::detect(os, arch));
+    }
+
+    #[test_case("macos", "x86_64", false, Platform::MacOs ; "macos intel")]
+    #[test_case("macos", "x86_64", true, Platform::MacOsArm64 ; "macos rosetta")]
+    #[test_case("macos", "aarch64", false, Platform::MacOsArm64 ; "macos apple silicon")]
+    #[test_case("windows", "x86_64", true, Platform::Windows64 ; "only on macos")]
+    fn test_detect_native_platform(os: &str, arch: &str, is_translated: bool, expected: Platform) {
+        assert_eq!(
+            Some(expected),
+            Platform::detect_native(os, arch, is_translated)
+        );
+    }
+
+    #[test_case("windows", "x86", "win32" ; "windows 32 bits")]
+    #[test_case("windows", "x86_64", "win64" ; "windows 64 bits")]
+    #[test_case("macos", "x86_64", "mac-x64" ; "macos intel")]
+    #[test_case("macos", "aarch64", "mac-arm64" ; "macos apple silicon")]
+    #[test_case("linux", "x86_64", "linux64" ; "linux")]
+    #[test_case("linux", "aarch64", "linux-arm64" ; "linux arm64")]
+    fn test_chrome_for_testing_key(os: &str, arch: &str, expected: &str) {
+        let platform = Platform::detect(os, arch).unwrap();
+
+        assert_eq!(expected, platform.get_chrome_for_testing_key());
+    }
+
+    #[test_case("windows", Some(Platform::Windows) ; "windows")]
+    #[test_case("win32", Some(Platform::Windows) ; "win32")]
+    #[test_case("win64", Some(Platform::Windows64) ; "win64")]
+    #[test_case("mac64", Some(Platform::MacOs) ; "mac64")]
+    #[test_case("mac-x64", Some(Platform::MacOs) ; "mac x64")]
+    #[test_case("mac-arm64", Some(Platform::MacOsArm64) ; "mac arm64")]
+    #[test_case("linux64", Some(Platform::Linux) ; "linux64")]
+    #[test_case("linux-arm64", Some(Platform::LinuxArm64) ; "linux arm64")]
+    #[test_case("linux32", None ; "unsupported")]
+    fn test_platform_from_str(input: &str, expected: Option<Platform>) {
+        assert_eq!(expected, input.parse::<Platform>().ok());
+    }
+}
@@ -0,0 +1,294 @@
+//! Runs the browsers and the drivers to query their versions, without waiting forever for
+//! a wedged process or keeping an unbounded output.
+use crate::Error;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The size of the output kept from each stream, the rest is discarded.
+const MAX_OUTPUT_SIZE: u64 = 64 * 1024;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static TIMEOUT_MILLISECONDS: AtomicU64 = AtomicU64::new(10_000);
+
+/// Set how long the programs run to query a version can take before being killed,
+/// 10 seconds by default.
+pub fn set_program_timeout(timeout: Duration) {
+    TIMEOUT_MILLISECONDS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+pub(crate) fn get_program_timeout() -> Duration {
+    Duration::from_millis(TIMEOUT_MILLISECONDS.load(Ordering::Relaxed))
+}
+
+/// Run the command and return its stdout and stderr, killing it if it is still running
+/// after the timeout set by [`set_program_timeout`].
+pub(crate) fn get_output(
+    program_path: &Path,
+    command: &mut Command,
+) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    get_output_with_timeout(program_path, command, get_program_timeout())
+}
+
+fn get_output_with_timeout(
+    program_path: &Path,
+    command: &mut Command,
+    timeout: Duration,
+) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| Error::CantRunProgram(program_path.to_path_buf(), error))?;
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    wait_with_timeout(program_path, &mut child, timeout)?;
+
+    Ok((
+        stdout.join().unwrap_or_default(),
+        stderr.join().unwrap_or_default(),
+    ))
+}
+
+/// Start the command and wait for a line of its stdout containing `expected`, then kill it.
+/// Killing is a `SIGKILL` on Unix and `TerminateProcess` on Windows, a driver has nothing to
+/// clean up. Fails if the program exits or `timeout` expires before printing the line.
+pub(crate) fn wait_for_line(
+    program_path: &Path,
+    command: &mut Command,
+    expected: &str,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| Error::CantRunProgram(program_path.to_path_buf(), error))?;
+    let lines = read_lines_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let deadline = Instant::now() + timeout;
+    let to_error = |reason: String| Error::SmokeTestFailed(program_path.to_path_buf(), reason);
+
+    let result = loop {
+        match lines.recv_timeout(POLL_INTERVAL) {
+            Ok(line) if line.contains(expected) => break Ok(()),
+            Ok(_) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => thread::sleep(POLL_INTERVAL),
+        }
+
+        if let Some(status) = child.try_wait()? {
+            let stderr = stderr.join().unwrap_or_default();
+
+            return Err(to_error(format!(
+                "it exited with {} before printing '{}'{}",
+                status,
+                expected,
+                format_stderr(&stderr)
+            )));
+        }
+
+        if Instant::now() >= deadline {
+            break Err(to_error(format!(
+                "it did not print '{}' within {:?}",
+                expected, timeout
+            )));
+        }
+    };
+
+    let _ = child.kill();
+    let _ = child.wait();
+
+    result
+}
+
+fn format_stderr(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let stderr = stderr.trim();
+
+    if stderr.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", stderr)
+    }
+}
+
+/// Each line is sent as soon as it is read, the rest of the output is discarded once
+/// [`MAX_OUTPUT_SIZE`] is read.
+fn read_lines_in_background<R: Read + Send + 'static>(stream: Option<R>) -> mpsc::Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        if let Some(mut stream) = stream {
+            for line in BufReader::new(stream.by_ref().take(MAX_OUTPUT_SIZE)).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+
+            let _ = std::io::copy(&mut stream, &mut std::io::sink());
+        }
+    });
+
+    receiver
+}
+
+/// The stream is drained until its end so the child never blocks on a full pipe.
+fn read_in_background<R: Read + Send + 'static>(stream: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+
+        if let Some(mut stream) = stream {
+            let _ = stream
+                .by_ref()
+                .take(MAX_OUTPUT_SIZE)
+                .read_to_end(&mut output);
+            let _ = std::io::copy(&mut stream, &mut std::io::sink());
+        }
+
+        output
+    })
+}
+
+fn wait_with_timeout(
+    program_path: &Path,
+    child: &mut Child,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        if child.try_wait()?.is_some() {
+            return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+
+            return Err(Error::ProgramTimedOut(program_path.to_path_buf()));
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::process::{get_output_with_timeout, wait_for_line, MAX_OUTPUT_SIZE};
+    use crate::Error;
+    use std::io::ErrorKind;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+    use test_case::test_case;
+
+    #[test]
+    fn test_get_output() {
+        let mut command = Command::new("sh");
+
+        command.args(["-c", "echo out; echo err >&2"]);
+
+        let (stdout, stderr) =
+            get_output_with_timeout(Path::new("sh"), &mut command, Duration::from_secs(10))
+                .unwrap();
+
+        assert_eq!(b"out\n".as_slice(), stdout);
+        assert_eq!(b"err\n".as_slice(), stderr);
+    }
+
+    #[test]
+    fn test_get_output_times_out() {
+        let mut command = Command::new("sh");
+
+        command.args(["-c", "sleep 10"]);
+
+        let start = Instant::now();
+        let result =
+            get_output_with_timeout(Path::new("sh"), &mut command, Duration::from_millis(100));
+
+        assert!(matches!(result, Err(Error::ProgramTimedOut(_))));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_get_output_is_capped() {
+        let mut command = Command::new("sh");
+
+        command.args(["-c", "head -c 1000000 /dev/zero"]);
+
+        let (stdout, _) =
+            get_output_with_timeout(Path::new("sh"), &mut command, Duration::from_secs(10))
+                .unwrap();
+
+        assert_eq!(MAX_OUTPUT_SIZE as usize, stdout.len());
+    }
+
+    #[test_case("echo starting; echo started; exec sleep 10", None ; "started")]
+    #[test_case("echo 'missing library' >&2; exit 127", Some("exit status: 127 before printing 'started': missing library") ; "exited")]
+    #[test_case("exec sleep 10", Some("did not print 'started' within 100ms") ; "timed out")]
+    fn test_wait_for_line(script: &str, expected_error: Option<&str>) {
+        let mut command = Command::new("sh");
+
+        command.args(["-c", script]);
+
+        let start = Instant::now();
+        let result = wait_for_line(
+            Path::new("sh"),
+            &mut command,
+            "started",
+            Duration::from_millis(100),
+        );
+
+        match (result, expected_error) {
+            (Ok(()), None) => {}
+            (Err(Error::SmokeTestFailed(_, reason)), Some(expected)) => {
+                assert!(reason.contains(expected), "{}", reason)
+            }
+            (result, _) => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test_case(None, ErrorKind::NotFound, "does not exist" ; "missing file")]
+    #[test_case(Some("data"), ErrorKind::PermissionDenied, "executable" ; "not executable")]
+    #[test_case(Some("#!/missing/interpreter\n"), ErrorKind::NotFound, "interpreter" ; "missing interpreter")]
+    fn test_get_output_error(content: Option<&str>, expected_kind: ErrorKind, expected_hint: &str) {
+        let directory = tempfile::tempdir().unwrap();
+        let program_path = directory.path().join("program");
+
+        if let Some(content) = content {
+            std::fs::write(&program_path, content).unwrap();
+        }
+
+        if content.is_some_and(|content| content.starts_with("#!")) {
+            std::fs::set_permissions(&program_path, std::fs::Permissions::from_mode(0o755))
+                .unwrap();
+        }
+
+        let error = get_output_with_timeout(
+            &program_path,
+            &mut Command::new(&program_path),
+            Duration::from_secs(10),
+        )
+        .unwrap_err();
+
+        match &error {
+            Error::CantRunProgram(_, source) => assert_eq!(expected_kind, source.kind()),
+            error => panic!("Unexpected error: {}", error),
+        }
+        assert!(error.to_string().contains(expected_hint), "{}", error);
+    }
+}
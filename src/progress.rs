@@ -0,0 +1,64 @@
+//! Progress of the download, drawn on stderr.
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// A bar when the size of the download is known, a spinner with the transferred bytes otherwise.
+/// The bars of the concurrent downloads are drawn one per line, after their `label` if any.
+pub fn make_progress_bar(
+    content_length: Option<u64>,
+    visible: bool,
+    label: Option<&str>,
+) -> ProgressBar {
+    static BARS: OnceLock<MultiProgress> = OnceLock::new();
+
+    if !visible {
+        return ProgressBar::hidden();
+    }
+
+    let progress_bar = match content_length {
+        Some(content_length) => ProgressBar::new(content_length).with_style(
+            ProgressStyle::with_template(
+                "{prefix}{bar:40} {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+            )
+            .expect("Invalid progress bar template"),
+        ),
+        None => ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("{prefix}{spinner} {bytes} ({bytes_per_sec})")
+                .expect("Invalid spinner template"),
+        ),
+    };
+
+    if let Some(label) = label {
+        progress_bar.set_prefix(format!("{} ", label));
+    }
+
+    BARS.get_or_init(MultiProgress::new).add(progress_bar)
+}
+
+/// The summary of a complete download, like `Downloaded 8.47 MiB in 2.1s (4.03 MiB/s)`,
+/// to diagnose a slow mirror.
+pub fn format_summary(size: u64, elapsed: Duration) -> String {
+    let throughput = size as f64 / elapsed.as_secs_f64().max(0.001);
+
+    format!(
+        "Downloaded {} in {:.1?} ({}/s)",
+        HumanBytes(size),
+        elapsed,
+        HumanBytes(throughput as u64)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::progress::format_summary;
+    use std::time::Duration;
+    use test_case::test_case;
+
+    #[test_case(8 * 1024 * 1024, Duration::from_secs(2), "Downloaded 8.00 MiB in 2.0s (4.00 MiB/s)" ; "basic")]
+    #[test_case(512, Duration::from_millis(250), "Downloaded 512 B in 250.0ms (2.00 KiB/s)" ; "small")]
+    #[test_case(1024, Duration::ZERO, "Downloaded 1.00 KiB in 0.0ns (1000.00 KiB/s)" ; "instant")]
+    fn test_format_summary(size: u64, elapsed: Duration, expected: &str) {
+        assert_eq!(expected, format_summary(size, elapsed));
+    }
+}
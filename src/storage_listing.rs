@@ -0,0 +1,193 @@
+//! XML listing of the legacy storage, the last resort to find the driver of a version when
+//! none of the `LATEST_RELEASE_<prefix>` files is published.
+use crate::{Error, HttpClient, Version};
+use log::debug;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// A page of the listing, the storage returns at most 1000 entries per page.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Listing {
+    /// The versions of the directories of the bucket, the other directories are ignored.
+    pub versions: Vec<Version>,
+    /// Where the next page starts if the listing is truncated.
+    pub next_marker: Option<String>,
+}
+
+/// Find the newest version published for `major.minor.build` in the listing, then for
+/// `major.minor`, then for `major`, like the `LATEST_RELEASE_<prefix>` files.
+pub(crate) fn get_driver_version(
+    client: &HttpClient,
+    base_url: &str,
+    chrome_version: Version,
+) -> Result<Version, Error> {
+    let mut versions = Vec::new();
+    let mut marker = None;
+
+    loop {
+        let mut url = format!("{}/?delimiter=/&prefix=", base_url);
+
+        if let Some(marker) = &marker {
+            url.push_str(&format!("&marker={}", marker));
+        }
+
+        let listing = parse_listing(&client.get_text(&url)?)?;
+
+        versions.extend(listing.versions);
+
+        match listing.next_marker {
+            Some(next_marker) if Some(&next_marker) != marker.as_ref() => {
+                marker = Some(next_marker)
+            }
+            _ => break,
+        }
+    }
+
+    debug!("{} versions in the listing of the storage", versions.len());
+
+    select_version(&versions, chrome_version).ok_or(Error::NoDriverForVersion(chrome_version))
+}
+
+/// Parse the `ListBucketResult` returned with `?delimiter=/&prefix=`, where each version is
+/// a `CommonPrefixes` like `<Prefix>114.0.5735.90/</Prefix>`.
+pub(crate) fn parse_listing(input: &str) -> Result<Listing, Error> {
+    let mut reader = Reader::from_str(input);
+    let mut path: Vec<String> = Vec::new();
+    let mut listing = Listing::default();
+    let mut is_truncated = false;
+
+    reader.config_mut().trim_text(true);
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => path.push(start.local_name().as_ref().to_string()),
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Text(text) => {
+                let text = text.xml10_content();
+
+                match path
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .as_slice()
+                {
+                    [.., "CommonPrefixes", "Prefix"] => {
+                        if let Ok(version) = text.trim_end_matches('/').parse() {
+                            listing.versions.push(version);
+                        }
+                    }
+                    [.., "IsTruncated"] => is_truncated = text == "true",
+                    [.., "NextMarker"] => listing.next_marker = Some(text.into_owned()),
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !is_truncated {
+        listing.next_marker = None;
+    }
+
+    Ok(listing)
+}
+
+pub(crate) fn select_version(versions: &[Version], chrome_version: Version) -> Option<Version> {
+    let matches: [fn(&Version, &Version) -> bool; 3] = [
+        |version, chrome_version| {
+            (version.major, version.minor, version.build)
+                == (
+                    chrome_version.major,
+                    chrome_version.minor,
+                    chrome_version.build,
+                )
+        },
+        |version, chrome_version| {
+            (version.major, version.minor) == (chrome_version.major, chrome_version.minor)
+        },
+        |version, chrome_version| version.major == chrome_version.major,
+    ];
+
+    matches.iter().find_map(|matches| {
+        versions
+            .iter()
+            .filter(|version| matches(version, &chrome_version))
+            .max()
+            .copied()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage_listing::{get_driver_version, parse_listing, select_version};
+    use crate::{test_server, Error, HttpClient, HttpOptions, Version};
+    use test_case::test_case;
+
+    /// Captured from https://chromedriver.storage.googleapis.com/?delimiter=/&prefix= and
+    /// shortened.
+    const LISTING: &str = r#"<?xml version='1.0' encoding='UTF-8'?><ListBucketResult xmlns='http://doc.s3.amazonaws.com/2006-03-01'><Name>chromedriver</Name><Prefix></Prefix><Marker></Marker><Delimiter>/</Delimiter><IsTruncated>false</IsTruncated><Contents><Key>LATEST_RELEASE</Key><Generation>1685759101974710</Generation><MetaGeneration>1</MetaGeneration><LastModified>2023-06-03T02:25:01.994Z</LastModified><ETag>"719a3b491070d44735abdb5b2f5c3b3e"</ETag><Size>12</Size></Contents><Contents><Key>index.html</Key><Generation>1457048232567000</Generation><MetaGeneration>4</MetaGeneration><LastModified>2016-03-03T23:37:12.542Z</LastModified><ETag>"e6b0e1b03dd4e5ba08ee2b0e91c4e8f4"</ETag><Size>10927</Size></Contents><CommonPrefixes><Prefix>2.46/</Prefix></CommonPrefixes><CommonPrefixes><Prefix>112.0.5615.28/</Prefix></CommonPrefixes><CommonPrefixes><Prefix>112.0.5615.49/</Prefix></CommonPrefixes><CommonPrefixes><Prefix>113.0.5672.24/</Prefix></CommonPrefixes><CommonPrefixes><Prefix>113.0.5672.63/</Prefix></CommonPrefixes><CommonPrefixes><Prefix>114.0.5735.16/</Prefix></CommonPrefixes><CommonPrefixes><Prefix>114.0.5735.90/</Prefix></CommonPrefixes><CommonPrefixes><Prefix>icons/</Prefix></CommonPrefixes></ListBucketResult>"#;
+
+    #[test]
+    fn test_parse_listing() {
+        let listing = parse_listing(LISTING).unwrap();
+
+        assert_eq!(
+            vec![
+                Version::new(112, 0, 5615, 28),
+                Version::new(112, 0, 5615, 49),
+                Version::new(113, 0, 5672, 24),
+                Version::new(113, 0, 5672, 63),
+                Version::new(114, 0, 5735, 16),
+                Version::new(114, 0, 5735, 90),
+            ],
+            listing.versions
+        );
+        assert_eq!(None, listing.next_marker);
+    }
+
+    #[test_case("<IsTruncated>true</IsTruncated><NextMarker>114.0.5735.16/</NextMarker>", Some("114.0.5735.16/") ; "truncated")]
+    #[test_case("<IsTruncated>false</IsTruncated><NextMarker>114.0.5735.16/</NextMarker>", None ; "not truncated")]
+    fn test_parse_listing_next_marker(content: &str, expected: Option<&str>) {
+        let listing =
+            parse_listing(&format!("<ListBucketResult>{}</ListBucketResult>", content)).unwrap();
+
+        assert_eq!(expected.map(String::from), listing.next_marker);
+    }
+
+    #[test]
+    fn test_parse_listing_invalid() {
+        let result = parse_listing("<ListBucketResult><Prefix></ListBucketResult>");
+
+        assert!(matches!(result, Err(Error::XmlParsingFailed(_))));
+    }
+
+    #[test_case(Version::new(113, 0, 5672, 126), Some(Version::new(113, 0, 5672, 63)) ; "same build")]
+    #[test_case(Version::new(113, 0, 5700, 1), Some(Version::new(113, 0, 5672, 63)) ; "same minor")]
+    #[test_case(Version::new(112, 1, 0, 0), Some(Version::new(112, 0, 5615, 49)) ; "same major")]
+    #[test_case(Version::new(111, 0, 5563, 64), None ; "missing major")]
+    fn test_select_version(chrome_version: Version, expected: Option<Version>) {
+        let versions = parse_listing(LISTING).unwrap().versions;
+
+        assert_eq!(expected, select_version(&versions, chrome_version));
+    }
+
+    #[test]
+    fn test_get_driver_version_pages() {
+        let url = test_server::start(|path| {
+            let body = match path {
+                "/?delimiter=/&prefix=" => "<ListBucketResult><IsTruncated>true</IsTruncated><NextMarker>113.0.5672.24/</NextMarker><CommonPrefixes><Prefix>113.0.5672.24/</Prefix></CommonPrefixes></ListBucketResult>",
+                "/?delimiter=/&prefix=&marker=113.0.5672.24/" => "<ListBucketResult><IsTruncated>false</IsTruncated><CommonPrefixes><Prefix>113.0.5672.63/</Prefix></CommonPrefixes></ListBucketResult>",
+                _ => return (404, Vec::new()),
+            };
+
+            (200, body.as_bytes().to_vec())
+        });
+        let client = HttpClient::new(&HttpOptions::default()).unwrap();
+        let result = get_driver_version(&client, &url, Version::new(113, 0, 5672, 126));
+
+        assert_eq!(Version::new(113, 0, 5672, 63), result.unwrap());
+    }
+}
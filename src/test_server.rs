@@ -0,0 +1,110 @@
+//! A minimal HTTP server to test the network code without reaching the real endpoints.
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+/// A request received by the server.
+pub struct Request {
+    pub path: String,
+    /// The headers, with their names in lowercase.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The status, the headers and the body of a response.
+pub type Response = (u16, Vec<(&'static str, String)>, Vec<u8>);
+
+/// Start a server calling `handler` with the path of each request, it returns the status
+/// and the body of the response. Returns the URL of the server, without trailing slash.
+pub fn start<F>(mut handler: F) -> String
+where
+    F: FnMut(&str) -> (u16, Vec<u8>) + Send + 'static,
+{
+    start_with_handler(move |request| {
+        let (status, body) = handler(&request.path);
+
+        (status, Vec::new(), body)
+    })
+}
+
+/// Like [`start`] but the handler receives the whole request and can return headers.
+pub fn start_with_handler<F>(mut handler: F) -> String
+where
+    F: FnMut(&Request) -> Response + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut headers = Vec::new();
+            let mut header = String::new();
+
+            while reader.read_line(&mut header).unwrap() > 2 {
+                if let Some((name, value)) = header.trim_end().split_once(':') {
+                    headers.push((name.to_lowercase(), value.trim().to_string()));
+                }
+
+                header.clear();
+            }
+
+            let request = Request {
+                path: request_line.split(' ').nth(1).unwrap_or("/").to_string(),
+                headers,
+            };
+            let (status, headers, body) = handler(&request);
+
+            write!(stream, "HTTP/1.1 {} Status\r\n", status).unwrap();
+
+            // A larger Content-Length than the body simulates an interrupted download.
+            let has_content_length = headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("content-length"));
+
+            for (name, value) in headers {
+                write!(stream, "{}: {}\r\n", name, value).unwrap();
+            }
+
+            if !has_content_length {
+                write!(stream, "Content-Length: {}\r\n", body.len()).unwrap();
+            }
+
+            write!(stream, "Connection: close\r\n\r\n").unwrap();
+            // The client may close the connection of an interrupted download first.
+            let _ = stream.write_all(&body);
+        }
+    });
+
+    url
+}
+
+/// Start a server answering each request with the next status of `statuses`.
+pub fn start_with_statuses(statuses: &'static [u16]) -> String {
+    let mut statuses = statuses.iter();
+
+    start(move |_| (*statuses.next().unwrap_or(&500), b"ok".to_vec()))
+}
+
+/// Start a server answering the requests matching one of the `routes`, and 404 otherwise.
+pub fn start_with_routes(routes: &'static [(&'static str, &'static str)]) -> String {
+    start(move |path| {
+        routes
+            .iter()
+            .find(|(route, _)| *route == path)
+            .map(|(_, body)| (200, body.as_bytes().to_vec()))
+            .unwrap_or((404, Vec::new()))
+    })
+}
@@ -0,0 +1,79 @@
+//! Durations of the phases of an update, to find which one is slow in an environment.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The total duration of each phase, by name, like `download` or `verification`. Shared by
+/// reference with the steps of an update, which add the duration of their phases.
+#[derive(Debug, Default)]
+pub struct Timings {
+    phases: Mutex<BTreeMap<&'static str, Duration>>,
+}
+
+/// The phases measured so far, like the lookup shared by the updates of several platforms.
+impl Clone for Timings {
+    fn clone(&self) -> Self {
+        Self {
+            phases: Mutex::new(
+                self.phases
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner())
+                    .clone(),
+            ),
+        }
+    }
+}
+
+impl Timings {
+    /// Add `duration` to the total of `phase`, a phase may happen several times.
+    pub fn add(&self, phase: &'static str, duration: Duration) {
+        let mut phases = self
+            .phases
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+
+        *phases.entry(phase).or_default() += duration;
+    }
+
+    /// Run `function` and add its duration to `phase`.
+    pub fn measure<T, F: FnOnce() -> T>(&self, phase: &'static str, function: F) -> T {
+        let start = Instant::now();
+        let result = function();
+
+        self.add(phase, start.elapsed());
+        result
+    }
+
+    /// The total of each phase, in milliseconds.
+    pub fn to_millis(&self) -> BTreeMap<&'static str, u64> {
+        self.phases
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .iter()
+            .map(|(phase, duration)| (*phase, duration.as_millis() as u64))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Timings;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timings() {
+        let timings = Timings::default();
+
+        timings.add("download", Duration::from_millis(120));
+        timings.add("download", Duration::from_millis(30));
+        assert_eq!(42, timings.measure("extraction", || 42));
+
+        let millis = timings.to_millis();
+
+        assert_eq!(
+            BTreeMap::from([("download", 150), ("extraction", millis["extraction"])]),
+            millis
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{parsers, Error};
+use nom::combinator::all_consuming;
+use nom::Finish;
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Version
+///
+/// https://www.chromium.org/developers/version-numbers/
+///
+/// Versions are ordered by comparing major, minor, build and patch in that order,
+/// so the declaration order of the fields matters.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub fn new(major: u32, minor: u32, build: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            build,
+            patch,
+        }
+    }
+
+    /// Parse a version where the patch number can be omitted, like `120.0.6099`.
+    pub fn from_partial_str(input: &str) -> Result<Self, Error> {
+        all_consuming(parsers::parse_partial_version_numbers)(input)
+            .finish()
+            .map_err(|_| Error::ParsingVersionFailed(format!("'{}' is not a valid version", input)))
+            .map(|(_, version)| version)
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        all_consuming(parsers::parse_version_numbers)(input)
+            .finish()
+            .map_err(|_| Error::ParsingVersionFailed(format!("'{}' is not a valid version", input)))
+            .map(|(_, version)| version)
+    }
+}
+
+/// Serialized as a string like `114.0.5735.90`.
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.patch
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Version;
+    use std::collections::HashSet;
+    use test_case::test_case;
+
+    #[test_case("114.0.5735.90", Some(Version::new(114, 0, 5735, 90)) ; "basic")]
+    #[test_case("114.0.5735", None ; "missing patch")]
+    #[test_case("114.0.5735.90 ", None ; "trailing data")]
+    #[test_case("", None ; "empty")]
+    fn test_version_from_str(input: &str, expected: Option<Version>) {
+        assert_eq!(expected, input.parse::<Version>().ok());
+    }
+
+    #[test_case("113.0.5672.63", "114.0.5735.90" ; "major")]
+    #[test_case("114.0.5735.90", "114.1.0.0" ; "minor")]
+    #[test_case("114.0.5735.90", "114.0.5736.0" ; "build")]
+    #[test_case("114.0.5735.16", "114.0.5735.90" ; "patch")]
+    #[test_case("114.0.6000.1", "115.0.5000.2" ; "major wins over build")]
+    fn test_version_ordering(lower: &str, greater: &str) {
+        let lower: Version = lower.parse().unwrap();
+        let greater: Version = greater.parse().unwrap();
+
+        assert!(lower < greater);
+    }
+
+    #[test]
+    fn test_serialize_version() {
+        let json = serde_json::to_string(&Version::new(114, 0, 5735, 90)).unwrap();
+
+        assert_eq!("\"114.0.5735.90\"", json);
+    }
+
+    #[test]
+    fn test_versions_in_hash_set() {
+        let version = Version::new(114, 0, 5735, 90);
+        let versions = HashSet::from([
+            version,
+            version,
+            Version::new(114, 0, 5735, 90),
+            Version::new(120, 0, 6099, 109),
+        ]);
+
+        assert_eq!(2, versions.len());
+        assert!(versions.contains(&version));
+    }
+}
@@ -0,0 +1,191 @@
+//! Installation of each driver version in its own directory of the output directory, like
+//! `120.0.6099.109/chromedriver`, with `latest` pointing at the newest one.
+use crate::{Error, Version};
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LATEST_DIRECTORY_NAME: &str = "latest";
+
+/// Where the driver of `version` is installed.
+pub fn get_version_directory(output_directory: &Path, version: Version) -> PathBuf {
+    output_directory.join(version.to_string())
+}
+
+/// The directory pointing at the newest installed version.
+pub fn get_latest_directory(output_directory: &Path) -> PathBuf {
+    output_directory.join(LATEST_DIRECTORY_NAME)
+}
+
+/// Point `latest` at the directory of `version`, with a relative symbolic link on Unix.
+/// On Windows, where creating symbolic links requires privileges, the files are copied.
+pub fn set_latest_version(output_directory: &Path, version: Version) -> Result<(), Error> {
+    let latest_directory = get_latest_directory(output_directory);
+
+    replace_latest(
+        &get_version_directory(output_directory, version),
+        &latest_directory,
+    )?;
+    info!("'{}' is now {}", latest_directory.display(), version);
+
+    Ok(())
+}
+
+/// The link is created next to the current one and renamed over it, so `latest` always exists.
+#[cfg(unix)]
+fn replace_latest(version_directory: &Path, latest_directory: &Path) -> Result<(), Error> {
+    let temporary_link = latest_directory.with_extension("new");
+    let target = version_directory
+        .file_name()
+        .expect("The version directory has a name");
+
+    remove_if_exists(&temporary_link)?;
+    std::os::unix::fs::symlink(target, &temporary_link)?;
+    fs::rename(&temporary_link, latest_directory)?;
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn replace_latest(version_directory: &Path, latest_directory: &Path) -> Result<(), Error> {
+    remove_if_exists(latest_directory)?;
+    fs::create_dir(latest_directory)?;
+
+    for entry in fs::read_dir(version_directory)? {
+        let entry = entry?;
+
+        if entry.file_type()?.is_file() {
+            fs::copy(entry.path(), latest_directory.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+/// The versions installed in the output directory, from the newest to the oldest.
+/// The directories not named after a version are ignored.
+pub fn get_installed_versions(output_directory: &Path) -> Result<Vec<Version>, Error> {
+    let mut versions = Vec::new();
+
+    for entry in fs::read_dir(output_directory)? {
+        let entry = entry?;
+
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        if let Some(version) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<Version>().ok())
+        {
+            versions.push(version);
+        }
+    }
+
+    versions.sort_by(|left, right| right.cmp(left));
+
+    Ok(versions)
+}
+
+/// Remove all the installed versions except the `keep` newest ones.
+/// Returns the removed versions.
+pub fn prune_versions(output_directory: &Path, keep: usize) -> Result<Vec<Version>, Error> {
+    let removed = get_installed_versions(output_directory)?
+        .into_iter()
+        .skip(keep)
+        .collect::<Vec<_>>();
+
+    for version in &removed {
+        fs::remove_dir_all(get_version_directory(output_directory, *version))?;
+        info!("Removed version {}", version);
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::versions::{get_installed_versions, prune_versions, set_latest_version};
+    use crate::Version;
+    use std::fs;
+    use test_case::test_case;
+
+    const VERSIONS: &[&str] = &[
+        "114.0.5735.90",
+        "120.0.6099.109",
+        "120.0.6099.71",
+        "119.0.6045.105",
+    ];
+
+    fn make_output_directory() -> tempfile::TempDir {
+        let output_directory = tempfile::tempdir().unwrap();
+
+        for version in VERSIONS {
+            let directory = output_directory.path().join(version);
+
+            fs::create_dir(&directory).unwrap();
+            fs::write(directory.join("chromedriver"), version).unwrap();
+        }
+
+        fs::create_dir(output_directory.path().join("other")).unwrap();
+        fs::write(output_directory.path().join("1.2.3.4"), "not a directory").unwrap();
+        output_directory
+    }
+
+    #[test]
+    fn test_get_installed_versions() {
+        let output_directory = make_output_directory();
+
+        assert_eq!(
+            vec![
+                Version::new(120, 0, 6099, 109),
+                Version::new(120, 0, 6099, 71),
+                Version::new(119, 0, 6045, 105),
+                Version::new(114, 0, 5735, 90),
+            ],
+            get_installed_versions(output_directory.path()).unwrap()
+        );
+    }
+
+    #[test_case(0, &[] ; "keep none")]
+    #[test_case(1, &["120.0.6099.109"] ; "keep newest")]
+    #[test_case(2, &["120.0.6099.109", "120.0.6099.71"] ; "patch order")]
+    #[test_case(3, &["120.0.6099.109", "120.0.6099.71", "119.0.6045.105"] ; "major order")]
+    #[test_case(10, &["120.0.6099.109", "120.0.6099.71", "119.0.6045.105", "114.0.5735.90"] ; "keep all")]
+    fn test_prune_versions(keep: usize, expected: &[&str]) {
+        let output_directory = make_output_directory();
+
+        prune_versions(output_directory.path(), keep).unwrap();
+
+        let remaining = get_installed_versions(output_directory.path())
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(expected, remaining);
+        assert!(output_directory.path().join("other").is_dir());
+    }
+
+    #[test]
+    fn test_set_latest_version() {
+        let output_directory = make_output_directory();
+        let latest = output_directory.path().join("latest").join("chromedriver");
+
+        set_latest_version(output_directory.path(), Version::new(119, 0, 6045, 105)).unwrap();
+        assert_eq!("119.0.6045.105", fs::read_to_string(&latest).unwrap());
+
+        set_latest_version(output_directory.path(), Version::new(120, 0, 6099, 109)).unwrap();
+        assert_eq!("120.0.6099.109", fs::read_to_string(&latest).unwrap());
+    }
+}
@@ -0,0 +1,634 @@
+//! Run the executable against a local server with the layout of chromedriver.storage.googleapis.com,
+//! used through --base-url for the drivers of Chrome 114 and older, and of Chrome for Testing.
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::net::TcpListener;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+const DRIVER_SCRIPT: &str = "#!/bin/sh\necho 'ChromeDriver 114.0.5735.90 (abc)'\n";
+
+type Routes = Vec<(&'static str, Vec<u8>)>;
+
+/// Serve the body of the first route whose path matches, 404 otherwise, and record the
+/// requested paths.
+fn start_server(routes: Routes) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let recorded_requests = requests.clone();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            answer(stream.unwrap(), &routes, &recorded_requests);
+        }
+    });
+
+    (url, requests)
+}
+
+/// Same as [`start_server`] over TLS, with the certificate of localhost signed by the CA of
+/// `tests/fixtures/ca.pem`. The failed handshakes are ignored.
+fn start_tls_server(routes: Routes) -> String {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let identity = native_tls::Identity::from_pkcs8(
+        &std::fs::read(fixtures.join("server.pem")).unwrap(),
+        &std::fs::read(fixtures.join("server-key.pem")).unwrap(),
+    )
+    .unwrap();
+    let acceptor = native_tls::TlsAcceptor::new(identity).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!(
+        "https://localhost:{}",
+        listener.local_addr().unwrap().port()
+    );
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            if let Ok(stream) = acceptor.accept(stream.unwrap()) {
+                answer(stream, &routes, &requests);
+            }
+        }
+    });
+
+    url
+}
+
+fn answer<S: Read + Write>(mut stream: S, routes: &Routes, requests: &Mutex<Vec<String>>) {
+    let mut reader = BufReader::new(&mut stream);
+    let mut request_line = String::new();
+    let mut header = String::new();
+
+    reader.read_line(&mut request_line).unwrap();
+
+    while reader.read_line(&mut header).unwrap() > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split(' ').nth(1).unwrap_or("/").to_string();
+    let (status, body) = routes
+        .iter()
+        .find(|(route, _)| path.ends_with(route))
+        .map(|(_, body)| (200, body.clone()))
+        .unwrap_or((404, Vec::new()));
+
+    requests.lock().unwrap().push(path);
+    write!(
+        stream,
+        "HTTP/1.1 {} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    )
+    .unwrap();
+    stream.write_all(&body).unwrap();
+}
+
+/// A legacy archive with the driver at its root, the driver only prints its version.
+fn make_archive() -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+
+    writer
+        .start_file(
+            "chromedriver",
+            zip::write::FileOptions::default().unix_permissions(0o755),
+        )
+        .unwrap();
+    writer.write_all(DRIVER_SCRIPT.as_bytes()).unwrap();
+    writer.finish().unwrap().into_inner()
+}
+
+fn install_driver(output_directory: &Path) {
+    let driver_path = output_directory.join("chromedriver");
+
+    std::fs::write(&driver_path, DRIVER_SCRIPT).unwrap();
+    std::fs::set_permissions(&driver_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// Run in the output directory, also used as home so no configuration file is found.
+fn run(base_url: &str, output_directory: &Path) -> std::process::Output {
+    run_with_arguments(base_url, output_directory, &[])
+}
+
+fn run_with_arguments(
+    base_url: &str,
+    output_directory: &Path,
+    arguments: &[&str],
+) -> std::process::Output {
+    make_command(base_url, output_directory, arguments)
+        .output()
+        .unwrap()
+}
+
+fn make_command(base_url: &str, output_directory: &Path, arguments: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_update_chrome_driver"));
+
+    command
+        .current_dir(output_directory)
+        .env("HOME", output_directory)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("CHROME_BIN")
+        .env_remove("CHROMEDRIVER_DIR")
+        .env_remove("GITHUB_OUTPUT")
+        .env_remove("CHROMEDRIVER_VERSION")
+        .env("NO_COLOR", "1")
+        .args(["--chrome-version", "114.0.5735.133", "--no-cache"])
+        .args(["--max-retries", "0", "--base-url", base_url])
+        .args(arguments)
+        .arg(output_directory);
+    command
+}
+
+fn get_driver_output(output_directory: &Path) -> String {
+    let output = Command::new(output_directory.join("chromedriver"))
+        .arg("--version")
+        .output()
+        .unwrap();
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_update() {
+    let (url, requests) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run(&url, output_directory.path());
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert!(get_driver_output(output_directory.path()).contains("114.0.5735.90"));
+    assert!(requests.lock().unwrap()[1].starts_with("/114.0.5735.90/chromedriver_"));
+}
+
+#[test]
+fn test_up_to_date() {
+    let (url, requests) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+
+    install_driver(output_directory.path());
+
+    let output = run(&url, output_directory.path());
+
+    assert_eq!(Some(0), output.status.code(), "{:?}", output);
+    assert_eq!(
+        vec![String::from("/LATEST_RELEASE_114.0.5735")],
+        *requests.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_concurrent_updates() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let children = (0..2)
+        .map(|_| {
+            make_command(&url, output_directory.path(), &[])
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let mut codes = children
+        .into_iter()
+        .map(|mut child| child.wait().unwrap().code())
+        .collect::<Vec<_>>();
+
+    // The second run waits for the first one, then finds the driver up to date.
+    codes.sort();
+    assert_eq!(vec![Some(0), Some(10)], codes);
+    assert!(get_driver_output(output_directory.path()).contains("114.0.5735.90"));
+}
+
+#[test]
+fn test_lock_timeout() {
+    let output_directory = tempfile::tempdir().unwrap();
+    let _lock = update_chrome_driver::lock_output_directory(
+        output_directory.path(),
+        std::time::Duration::ZERO,
+    )
+    .unwrap();
+    let output = run_with_arguments(
+        "http://127.0.0.1:1",
+        output_directory.path(),
+        &["--lock-timeout", "0"],
+    );
+
+    assert_eq!(Some(1), output.status.code(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Another update of"));
+}
+
+#[test]
+fn test_pinned_version() {
+    let (url, requests) = start_server(vec![(".zip", make_archive())]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = make_command(&url, output_directory.path(), &[])
+        .env("CHROMEDRIVER_VERSION", "114.0.5735.90")
+        .output()
+        .unwrap();
+
+    // Nothing is looked up for the browser, the archive of the pinned version is downloaded.
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert!(requests.lock().unwrap()[0].starts_with("/114.0.5735.90/chromedriver_"));
+
+    let output = make_command(
+        &url,
+        output_directory.path(),
+        &[
+            "--chromedriver-version",
+            "114.0.5735.16",
+            "--force",
+            "--no-verify",
+        ],
+    )
+    .env("CHROMEDRIVER_VERSION", "114.0.5735.90")
+    .output()
+    .unwrap();
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert!(requests.lock().unwrap()[1].starts_with("/114.0.5735.16/chromedriver_"));
+}
+
+#[test]
+fn test_post_install_cmd() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let hook_output = output_directory.path().join("hook_output");
+    let command = format!("echo \"$CHROMEDRIVER_PATH\" >> '{}'", hook_output.display());
+    let arguments = ["--post-install-cmd", command.as_str()];
+    let output = run_with_arguments(&url, output_directory.path(), &arguments);
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert_eq!(
+        format!(
+            "{}\n",
+            output_directory.path().join("chromedriver").display()
+        ),
+        std::fs::read_to_string(&hook_output).unwrap()
+    );
+
+    // Not run again when the driver is up to date.
+    let output = run_with_arguments(&url, output_directory.path(), &arguments);
+
+    assert_eq!(Some(0), output.status.code(), "{:?}", output);
+    assert_eq!(
+        1,
+        std::fs::read_to_string(&hook_output)
+            .unwrap()
+            .lines()
+            .count()
+    );
+}
+
+#[test]
+fn test_post_install_cmd_failure() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run_with_arguments(
+        &url,
+        output_directory.path(),
+        &["--post-install-cmd", "exit 3"],
+    );
+
+    assert_eq!(Some(1), output.status.code(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("The post-install command 'exit 3' failed: exit status: 3"));
+}
+
+#[test]
+fn test_json_revision() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+
+    install_driver(output_directory.path());
+
+    let output = run_with_arguments(&url, output_directory.path(), &["--output", "json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(Some(0), output.status.code(), "{:?}", output);
+    assert_eq!("114.0.5735.90", report["current_version"]);
+    assert_eq!("abc", report["current_revision"]);
+}
+
+#[test]
+fn test_json_timings() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run_with_arguments(&url, output_directory.path(), &["--output", "json"]);
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut phases = report["timings_ms"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    // With --chrome-version, the browser detection only reads the option.
+    phases.sort();
+    assert_eq!(
+        vec![
+            "browser_detection",
+            "download",
+            "extraction",
+            "verification",
+            "version_lookup"
+        ],
+        phases
+    );
+}
+
+#[test]
+fn test_platform_key() {
+    let (url, requests) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run_with_arguments(
+        &url,
+        output_directory.path(),
+        &["--platform-key", "linux-arm64", "--no-verify"],
+    );
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert_eq!(
+        "/114.0.5735.90/chromedriver_linux-arm64.zip",
+        requests.lock().unwrap()[1]
+    );
+    assert!(output_directory.path().join("chromedriver").is_file());
+}
+
+#[test]
+fn test_chrome_for_testing_mirror() {
+    let (url, requests) = start_server(vec![
+        (
+            "/known-good-versions-with-downloads.json",
+            br#"{"versions": [{"version": "120.0.6099.109", "downloads": {"chromedriver": [
+                {"platform": "linux64", "url": "https://storage.googleapis.com/chromedriver-linux64.zip"}
+            ]}}]}"#
+                .to_vec(),
+        ),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run_with_arguments(
+        &url,
+        output_directory.path(),
+        &[
+            "--exact-version",
+            "120.0.6099.109",
+            "--platform",
+            "linux64",
+            "--no-verify",
+        ],
+    );
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert_eq!(
+        vec![
+            "/known-good-versions-with-downloads.json",
+            "/120.0.6099.109/linux64/chromedriver-linux64.zip"
+        ],
+        *requests.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_platform_key_empty() {
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run_with_arguments(
+        "http://127.0.0.1:1",
+        output_directory.path(),
+        &["--platform-key", ""],
+    );
+
+    assert_eq!(Some(2), output.status.code(), "{:?}", output);
+}
+
+#[test]
+fn test_github_output() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let github_output = output_directory.path().join("github_output");
+    let output = make_command(&url, output_directory.path(), &["--github-output"])
+        .env("GITHUB_OUTPUT", &github_output)
+        .output()
+        .unwrap();
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert_eq!(
+        "required_version=114.0.5735.90\ncurrent_version=\nupdated=true\n",
+        std::fs::read_to_string(github_output).unwrap()
+    );
+}
+
+#[test]
+fn test_no_extract() {
+    // Built once, the entries of two archives may have different modification times.
+    let archive = make_archive();
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", archive.clone()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let archive_path = output_directory.path().join("archives/chromedriver.zip");
+    let output = run_with_arguments(
+        &url,
+        output_directory.path(),
+        &[
+            "--no-extract",
+            "--archive-path",
+            archive_path.to_str().unwrap(),
+        ],
+    );
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert_eq!(archive, std::fs::read(&archive_path).unwrap());
+    assert!(!output_directory.path().join("chromedriver").exists());
+}
+
+#[test]
+fn test_tls_ca_cert() {
+    let url = start_tls_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let ca_cert = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ca.pem");
+    let output = run_with_arguments(
+        &url,
+        output_directory.path(),
+        &["--ca-cert", ca_cert.to_str().unwrap()],
+    );
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert!(get_driver_output(output_directory.path()).contains("114.0.5735.90"));
+}
+
+#[test]
+fn test_tls_untrusted() {
+    let url = start_tls_server(vec![(
+        "/LATEST_RELEASE_114.0.5735",
+        b"114.0.5735.90".to_vec(),
+    )]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run(&url, output_directory.path());
+
+    assert_eq!(Some(4), output.status.code(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("TLS error"));
+}
+
+#[test]
+fn test_tls_insecure() {
+    let url = start_tls_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run_with_arguments(&url, output_directory.path(), &["--insecure"]);
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("not verified"));
+}
+
+#[test]
+fn test_dry_run_lists_files() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+
+    install_driver(output_directory.path());
+    std::fs::write(
+        output_directory.path().join("chromedriver"),
+        "#!/bin/sh\necho 'ChromeDriver 113.0.5672.63 (abc)'\n",
+    )
+    .unwrap();
+
+    let output = run_with_arguments(&url, output_directory.path(), &["--dry-run"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(Some(0), output.status.code(), "{:?}", output);
+    assert!(stdout.contains("Would overwrite: "), "{}", stdout);
+    assert_eq!(1, stdout.matches(".zip").count(), "{}", stdout);
+    assert!(get_driver_output(output_directory.path()).contains("113.0.5672.63"));
+}
+
+/// The installed driver is of `version`, next to the license file of its archive.
+fn install_previous_driver(output_directory: &Path, version: &str) {
+    install_driver(output_directory);
+    std::fs::write(
+        output_directory.join("chromedriver"),
+        format!("#!/bin/sh\necho 'ChromeDriver {} (abc)'\n", version),
+    )
+    .unwrap();
+    std::fs::write(output_directory.join("LICENSE.chromedriver"), "").unwrap();
+}
+
+#[test]
+fn test_prune_on_major_change() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+
+    install_previous_driver(output_directory.path(), "113.0.5672.63");
+
+    let output = run_with_arguments(&url, output_directory.path(), &["--prune-on-major-change"]);
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert!(get_driver_output(output_directory.path()).contains("114.0.5735.90"));
+    assert!(!output_directory
+        .path()
+        .join("LICENSE.chromedriver")
+        .exists());
+}
+
+#[test]
+fn test_prune_on_major_change_same_major() {
+    let (url, _) = start_server(vec![
+        ("/LATEST_RELEASE_114.0.5735", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+
+    install_previous_driver(output_directory.path(), "114.0.5735.16");
+
+    let output = run_with_arguments(&url, output_directory.path(), &["--prune-on-major-change"]);
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert!(output_directory
+        .path()
+        .join("LICENSE.chromedriver")
+        .is_file());
+}
+
+#[test]
+fn test_recently_modified() {
+    let (url, requests) = start_server(Vec::new());
+    let output_directory = tempfile::tempdir().unwrap();
+
+    install_driver(output_directory.path());
+
+    let output = run_with_arguments(&url, output_directory.path(), &["--if-newer-than", "1h"]);
+
+    assert_eq!(Some(0), output.status.code(), "{:?}", output);
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_lookup_falls_back_to_minor_version() {
+    let (url, requests) = start_server(vec![
+        ("/LATEST_RELEASE_114.0", b"114.0.5735.90".to_vec()),
+        (".zip", make_archive()),
+    ]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run(&url, output_directory.path());
+
+    assert_eq!(Some(10), output.status.code(), "{:?}", output);
+    assert!(get_driver_output(output_directory.path()).contains("114.0.5735.90"));
+    assert_eq!("/LATEST_RELEASE_114.0.5735", requests.lock().unwrap()[0]);
+}
+
+#[test]
+fn test_archive_not_found() {
+    let (url, _) = start_server(vec![(
+        "/LATEST_RELEASE_114.0.5735",
+        b"114.0.5735.90".to_vec(),
+    )]);
+    let output_directory = tempfile::tempdir().unwrap();
+    let output = run(&url, output_directory.path());
+
+    assert_eq!(Some(5), output.status.code(), "{:?}", output);
+    assert!(!output_directory.path().join("chromedriver").exists());
+}